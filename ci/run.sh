@@ -14,6 +14,7 @@ if [ -z "$NO_RUN" ]; then
       export LD_LIBRARY_PATH=${GETTEXT_DIR}/lib:$LD_LIBRARY_PATH
     fi
     cargo test --exclude systest --workspace --target $TARGET --verbose -- --nocapture
+    cargo test --package gettext-rs --features pure-rust --target $TARGET --verbose -- --nocapture
     cargo doc --no-deps --target $TARGET
     # We don't deny warnings here because we don't care about warnings in auto-generated code.
     cargo run --manifest-path systest/Cargo.toml --target $TARGET -vv
@@ -0,0 +1,400 @@
+//! Reader for GNU MO files.
+//!
+//! The format is described in the GNU gettext manual, section "The Format of GNU MO Files". In
+//! short, the file starts with a header that points to two tables of (length, offset) pairs: one
+//! for original strings, one for translations. Original strings are sorted, so they can be
+//! binary-searched; optionally, the file also contains a hash table to speed up lookups.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+use std::os::raw::{c_char, c_ulong};
+
+use plural::PluralForms;
+
+const MAGIC: u32 = 0x9504_12de;
+const MAGIC_SWAPPED: u32 = 0xde12_0495;
+const HEADER_LEN: usize = 28;
+
+/// Errors that might come up while parsing an MO file.
+#[derive(Debug, PartialEq)]
+pub enum CatalogError {
+    /// The file doesn't start with the MO magic number.
+    BadMagic,
+    /// The major revision of the file format is not supported.
+    UnsupportedRevision(u32),
+    /// A table or a string lies outside of the file, or a string is not 0-terminated.
+    Truncated,
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::CatalogError::*;
+
+        match self {
+            BadMagic => write!(f, "Not an MO file."),
+            UnsupportedRevision(revision) => {
+                write!(f, "Unsupported MO file revision {:#x}.", revision)
+            }
+            Truncated => write!(f, "The MO file is truncated or corrupted."),
+        }
+    }
+}
+
+impl error::Error for CatalogError {}
+
+/// A parsed MO file.
+///
+/// The catalog owns the raw bytes of the file (or borrows them, if they're embedded in the program)
+/// and hands out pointers into them, so the strings it returns are valid for as long as the catalog
+/// itself.
+pub struct Catalog {
+    data: Cow<'static, [u8]>,
+    big_endian: bool,
+    nstrings: usize,
+    originals: usize,
+    translations: usize,
+    hash_size: usize,
+    hash_table: usize,
+    plural_forms: PluralForms,
+}
+
+impl Catalog {
+    /// Parses an MO file in either byte order, checking that all the strings it references are
+    /// within bounds and 0-terminated.
+    pub fn parse<D: Into<Cow<'static, [u8]>>>(data: D) -> Result<Catalog, CatalogError> {
+        let data = data.into();
+        if data.len() < HEADER_LEN {
+            return Err(CatalogError::BadMagic);
+        }
+
+        let big_endian = match read_u32(&data, 0, false) {
+            MAGIC => false,
+            MAGIC_SWAPPED => true,
+            _ => return Err(CatalogError::BadMagic),
+        };
+
+        let mut catalog = Catalog {
+            data,
+            big_endian,
+            nstrings: 0,
+            originals: 0,
+            translations: 0,
+            hash_size: 0,
+            hash_table: 0,
+            plural_forms: PluralForms::default(),
+        };
+
+        let revision = catalog.word(4);
+        if revision >> 16 > 1 {
+            return Err(CatalogError::UnsupportedRevision(revision));
+        }
+
+        catalog.nstrings = catalog.word(8) as usize;
+        catalog.originals = catalog.word(12) as usize;
+        catalog.translations = catalog.word(16) as usize;
+        catalog.hash_size = catalog.word(20) as usize;
+        catalog.hash_table = catalog.word(24) as usize;
+
+        let tables_fit = |offset: usize, entries: usize, entry_len: usize| {
+            entries
+                .checked_mul(entry_len)
+                .and_then(|len| len.checked_add(offset))
+                .is_some_and(|end| end <= catalog.data.len())
+        };
+        if !tables_fit(catalog.originals, catalog.nstrings, 8)
+            || !tables_fit(catalog.translations, catalog.nstrings, 8)
+        {
+            return Err(CatalogError::Truncated);
+        }
+        // A hash table smaller than 3 entries can't be probed (see `find`), so we ignore it and
+        // use binary search instead.
+        if catalog.hash_size < 3 || !tables_fit(catalog.hash_table, catalog.hash_size, 4) {
+            catalog.hash_size = 0;
+        }
+
+        for index in 0..catalog.nstrings {
+            for &table in &[catalog.originals, catalog.translations] {
+                let (len, offset) = catalog.entry(table, index);
+                let terminated = offset
+                    .checked_add(len)
+                    .is_some_and(|end| catalog.data.get(end) == Some(&0));
+                if !terminated {
+                    return Err(CatalogError::Truncated);
+                }
+            }
+        }
+
+        // Like GNU gettext, fall back to the Germanic rule if the header is missing or malformed.
+        if let Some(index) = catalog.find(b"") {
+            let header = String::from_utf8_lossy(catalog.translation_bytes(index)).into_owned();
+            if let Ok(plural_forms) = PluralForms::from_header(&header) {
+                catalog.plural_forms = plural_forms;
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Returns the index of the plural form to use for `n`, as specified by the catalog's
+    /// `Plural-Forms` header.
+    pub fn plural_form(&self, n: u64) -> usize {
+        self.plural_forms.index(n)
+    }
+
+    /// Returns the index of the given `msgid` (without the trailing 0 byte), if present.
+    pub fn find(&self, msgid: &[u8]) -> Option<usize> {
+        if self.hash_size > 0 {
+            let hash = hash_string(msgid) as u32 as usize;
+            let mut idx = hash % self.hash_size;
+            let incr = 1 + hash % (self.hash_size - 2);
+
+            for _ in 0..self.hash_size {
+                let nstr = self.word(self.hash_table + idx * 4) as usize;
+                if nstr == 0 {
+                    return None;
+                }
+                if nstr - 1 < self.nstrings && self.msgid(nstr - 1) == msgid {
+                    return Some(nstr - 1);
+                }
+
+                if idx >= self.hash_size - incr {
+                    idx -= self.hash_size - incr;
+                } else {
+                    idx += incr;
+                }
+            }
+
+            None
+        } else {
+            let (mut low, mut high) = (0, self.nstrings);
+            while low < high {
+                let mid = (low + high) / 2;
+                match self.msgid(mid).cmp(msgid) {
+                    Ordering::Less => low = mid + 1,
+                    Ordering::Greater => high = mid,
+                    Ordering::Equal => return Some(mid),
+                }
+            }
+
+            None
+        }
+    }
+
+    /// Returns a pointer to the `form`-th plural form of the translation with the given index. If
+    /// there are fewer forms than that, the last one is returned.
+    pub fn translation(&self, index: usize, form: usize) -> *const c_char {
+        let (len, mut offset) = self.entry(self.translations, index);
+        let end = offset + len;
+        for _ in 0..form {
+            match self.data[offset..end].iter().position(|&byte| byte == 0) {
+                Some(position) => offset += position + 1,
+                None => break,
+            }
+        }
+
+        self.data[offset..].as_ptr() as *const c_char
+    }
+
+    /// Returns all forms of the translation with the given index, separated by 0 bytes.
+    fn translation_bytes(&self, index: usize) -> &[u8] {
+        let (len, offset) = self.entry(self.translations, index);
+        &self.data[offset..offset + len]
+    }
+
+    /// Returns the singular `msgid` of the string with the given index, without the plural form.
+    fn msgid(&self, index: usize) -> &[u8] {
+        let (len, offset) = self.entry(self.originals, index);
+        let original = &self.data[offset..offset + len];
+        match original.iter().position(|&byte| byte == 0) {
+            Some(position) => &original[..position],
+            None => original,
+        }
+    }
+
+    fn entry(&self, table: usize, index: usize) -> (usize, usize) {
+        let position = table + index * 8;
+        (
+            self.word(position) as usize,
+            self.word(position + 4) as usize,
+        )
+    }
+
+    fn word(&self, position: usize) -> u32 {
+        read_u32(&self.data, position, self.big_endian)
+    }
+}
+
+fn read_u32(data: &[u8], position: usize, big_endian: bool) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[position..position + 4]);
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// The hash function used by GNU gettext (a variant of hashpjw). It's computed in `unsigned long`
+/// in C, so we do the same to get the exact same results.
+fn hash_string(bytes: &[u8]) -> c_ulong {
+    const HASHWORDBITS: u32 = 32;
+
+    let mut hval: c_ulong = 0;
+    for &byte in bytes {
+        hval = (hval << 4).wrapping_add(c_ulong::from(byte));
+        let g = hval & (!0 << (HASHWORDBITS - 4));
+        if g != 0 {
+            hval ^= g >> (HASHWORDBITS - 8);
+            hval ^= g;
+        }
+    }
+
+    hval
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// Builds an MO file out of `(msgid, msgstr)` pairs, which must be sorted by `msgid`. Plural
+    /// forms are separated by 0 bytes, like in the file itself.
+    pub fn mo_file(entries: &[(&str, &str)], big_endian: bool, with_hash_table: bool) -> Vec<u8> {
+        let hash_size = if with_hash_table {
+            entries.len() * 2 + 3
+        } else {
+            0
+        };
+        let originals = HEADER_LEN;
+        let translations = originals + entries.len() * 8;
+        let hash_table = translations + entries.len() * 8;
+        let mut strings = hash_table + hash_size * 4;
+
+        let mut words = vec![
+            MAGIC,
+            0,
+            entries.len() as u32,
+            originals as u32,
+            translations as u32,
+            hash_size as u32,
+            hash_table as u32,
+        ];
+        let mut data = vec![];
+        for column in 0..2 {
+            for entry in entries {
+                let string = if column == 0 { entry.0 } else { entry.1 };
+                words.push(string.len() as u32);
+                words.push(strings as u32);
+                data.extend_from_slice(string.as_bytes());
+                data.push(0);
+                strings += string.len() + 1;
+            }
+        }
+
+        let mut hash = vec![0; hash_size];
+        if with_hash_table {
+            for (index, entry) in entries.iter().enumerate() {
+                let msgid = entry.0.split('\0').next().unwrap();
+                let hval = hash_string(msgid.as_bytes()) as u32 as usize;
+                let mut idx = hval % hash_size;
+                let incr = 1 + hval % (hash_size - 2);
+                while hash[idx] != 0 {
+                    idx = (idx + incr) % hash_size;
+                }
+                hash[idx] = index as u32 + 1;
+            }
+        }
+        words.extend(hash);
+
+        let mut file = vec![];
+        for word in words {
+            if big_endian {
+                file.extend_from_slice(&word.to_be_bytes());
+            } else {
+                file.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        file.extend(data);
+        file
+    }
+
+    fn translation(catalog: &Catalog, msgid: &str, form: usize) -> Option<String> {
+        catalog.find(msgid.as_bytes()).map(|index| unsafe {
+            CStr::from_ptr(catalog.translation(index, form))
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    #[test]
+    fn lookups_work_in_all_layouts() {
+        let entries = [
+            ("", "Content-Type: text/plain; charset=UTF-8\n"),
+            ("Hello, world!", "Bonjour, le monde !"),
+            ("One file\0Many files", "Un fichier\0Des fichiers"),
+            ("context\x04Open", "Ouvrir"),
+        ];
+
+        for &big_endian in &[false, true] {
+            for &with_hash_table in &[false, true] {
+                let file = mo_file(&entries, big_endian, with_hash_table);
+                let catalog = Catalog::parse(file).unwrap();
+
+                assert_eq!(
+                    translation(&catalog, "Hello, world!", 0).as_deref(),
+                    Some("Bonjour, le monde !")
+                );
+                assert_eq!(
+                    translation(&catalog, "context\x04Open", 0).as_deref(),
+                    Some("Ouvrir")
+                );
+                assert_eq!(
+                    translation(&catalog, "One file", 1).as_deref(),
+                    Some("Des fichiers")
+                );
+                assert_eq!(
+                    translation(&catalog, "One file", 5).as_deref(),
+                    Some("Des fichiers")
+                );
+                assert_eq!(translation(&catalog, "Missing", 0), None);
+            }
+        }
+    }
+
+    #[test]
+    fn plural_forms_come_from_the_header() {
+        let header = "Content-Type: text/plain; charset=UTF-8\n\
+                      Plural-Forms: nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;\n";
+        let file = mo_file(&[("", header), ("File\0Files", "A\0B\0C")], false, true);
+        let catalog = Catalog::parse(file).unwrap();
+        assert_eq!(catalog.plural_form(1), 0);
+        assert_eq!(catalog.plural_form(2), 1);
+        assert_eq!(catalog.plural_form(5), 2);
+
+        let file = mo_file(&[("File\0Files", "A\0B")], false, false);
+        let catalog = Catalog::parse(file).unwrap();
+        assert_eq!(catalog.plural_form(1), 0);
+        assert_eq!(catalog.plural_form(5), 1);
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        assert_eq!(
+            Catalog::parse(b"definitely not an MO file".to_vec()).err(),
+            Some(CatalogError::BadMagic)
+        );
+
+        let mut file = mo_file(&[("a", "b")], false, false);
+        file.truncate(file.len() - 1);
+        assert_eq!(Catalog::parse(file).err(), Some(CatalogError::Truncated));
+
+        let mut file = mo_file(&[("a", "b")], false, false);
+        file[6] = 2;
+        assert_eq!(
+            Catalog::parse(file).err(),
+            Some(CatalogError::UnsupportedRevision(0x20000))
+        );
+    }
+}
@@ -0,0 +1,721 @@
+//! A pure-Rust implementation of the parts of libintl that this crate uses.
+//!
+//! This module is used when the `pure-rust` feature is enabled. Its functions have the same
+//! signatures as their counterparts in `gettext-sys`, and the crate imports it under the `ffi`
+//! name, so the safe wrappers don't need to know which implementation they're calling.
+//!
+//! The behaviour follows GNU libintl:
+//!
+//! - `setlocale` is emulated: it only records the locale name for each category. An empty name is
+//!   resolved using `LC_ALL`, `LC_*`, and `LANG` environment variables. Programs start in the "C"
+//!   locale, in which messages are not translated;
+//! - catalogs are searched for in `dirname/language/LC_CATEGORY/domainname.mo`, where `dirname` is
+//!   set by `bindtextdomain` (and defaults to _/usr/share/locale_), and `language` goes through
+//!   the entries of the `LANGUAGE` environment variable (or just the locale name, if it's not
+//!   set), each of them stripped of codeset, territory, and modifier in the same order as GNU
+//!   gettext does;
+//! - the codeset set by `bind_textdomain_codeset` is recorded, but no conversion is performed, so
+//!   the catalogs have to be in UTF-8;
+//! - plural forms are chosen using the `Plural-Forms` header of the catalog, evaluated by the
+//!   [`plural`][::plural] module. Catalogs without that header use the Germanic rule, `n != 1`.
+//!
+//! `newlocale` and `uselocale` are emulated too: a locale object only records locale names, and the
+//! one set by `uselocale` takes precedence over the `setlocale` ones for lookups made from that
+//! thread.
+//!
+//! Catalogs embedded in the program are registered with [`register_file`] under made-up paths, and
+//! are looked up like any other file, except that the filesystem is never searched for them.
+//!
+//! Loaded catalogs are never unloaded, so the pointers returned by the lookup functions stay valid
+//! until the end of the program, just like with libintl.
+
+mod catalog;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(test)]
+pub use self::catalog::tests::mo_file;
+use self::catalog::Catalog;
+use embedded;
+use locale_name::{languages, locale_from_env, locale_variants, non_empty_var};
+
+/// Names of the locale categories, indexed by their values in [`LocaleCategory`][::LocaleCategory].
+const CATEGORY_NAMES: [&str; 13] = [
+    "LC_CTYPE",
+    "LC_NUMERIC",
+    "LC_TIME",
+    "LC_COLLATE",
+    "LC_MONETARY",
+    "LC_MESSAGES",
+    "LC_ALL",
+    "LC_PAPER",
+    "LC_NAME",
+    "LC_ADDRESS",
+    "LC_TELEPHONE",
+    "LC_MEASUREMENT",
+    "LC_IDENTIFICATION",
+];
+const LC_MESSAGES: c_int = 5;
+const LC_ALL: c_int = 6;
+
+#[allow(non_camel_case_types)]
+pub type locale_t = *mut c_void;
+pub const LC_GLOBAL_LOCALE: locale_t = -1isize as locale_t;
+// Same value as in glibc: a bit for each category, except for `LC_ALL`. The other masks aren't
+// used by the crate.
+pub const LC_ALL_MASK: c_int = 0x1fbf;
+
+const DEFAULT_DOMAIN: &[u8] = b"messages";
+const DEFAULT_DIRNAME: &str = "/usr/share/locale";
+
+#[derive(Default)]
+struct Binding {
+    dirname: Option<PathBuf>,
+    codeset: Option<&'static CStr>,
+    /// Catalogs bound with [`bind_catalog`], by locale category.
+    catalogs: HashMap<c_int, PathBuf>,
+}
+
+struct State {
+    /// Strings handed out to the callers. They're never freed, so the pointers stay valid even if
+    /// another thread changes the setting right after the call.
+    strings: HashMap<Vec<u8>, &'static CStr>,
+    #[cfg(windows)]
+    wide_strings: HashMap<Vec<u16>, &'static [u16]>,
+    textdomain: &'static CStr,
+    bindings: HashMap<Vec<u8>, Binding>,
+    locales: [&'static CStr; 13],
+    catalogs: HashMap<PathBuf, Option<&'static Catalog>>,
+    /// Contents of the MO files registered with [`register_file`], by their paths.
+    files: HashMap<PathBuf, &'static [u8]>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// A locale object created by [`newlocale`]; `locale_t` points to one of these.
+struct LocaleObject {
+    locales: [&'static CStr; 13],
+}
+
+thread_local! {
+    /// The locale set by [`uselocale`] for this thread.
+    static THREAD_LOCALE: Cell<locale_t> = const { Cell::new(LC_GLOBAL_LOCALE) };
+}
+
+struct StateGuard(MutexGuard<'static, Option<State>>);
+
+impl StateGuard {
+    fn get(&mut self) -> &mut State {
+        self.0.get_or_insert_with(State::new)
+    }
+}
+
+fn lock() -> StateGuard {
+    StateGuard(
+        STATE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    )
+}
+
+impl State {
+    fn new() -> State {
+        let mut strings = HashMap::new();
+        let c = intern_into(&mut strings, b"C");
+        let textdomain = intern_into(&mut strings, DEFAULT_DOMAIN);
+
+        State {
+            strings,
+            #[cfg(windows)]
+            wide_strings: HashMap::new(),
+            textdomain,
+            bindings: HashMap::new(),
+            locales: [c; 13],
+            catalogs: HashMap::new(),
+            files: HashMap::new(),
+        }
+    }
+
+    fn intern(&mut self, string: &[u8]) -> &'static CStr {
+        intern_into(&mut self.strings, string)
+    }
+
+    #[cfg(windows)]
+    fn intern_wide(&mut self, string: &[u16]) -> &'static [u16] {
+        if let Some(interned) = self.wide_strings.get(string) {
+            return interned;
+        }
+
+        let mut owned = string.to_vec();
+        owned.push(0);
+        let interned: &'static [u16] = Box::leak(owned.into_boxed_slice());
+        self.wide_strings.insert(string.to_vec(), interned);
+        interned
+    }
+
+    fn dirname(&self, domainname: &[u8]) -> PathBuf {
+        self.bindings
+            .get(domainname)
+            .and_then(|binding| binding.dirname.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DIRNAME))
+    }
+
+    fn set_locale(&mut self, category: c_int, locale: &[u8]) {
+        self.locales[category as usize] = self.resolve_locale(category, locale);
+    }
+
+    /// Interns the locale name for `category`, looking it up in the environment if it's empty.
+    fn resolve_locale(&mut self, category: c_int, locale: &[u8]) -> &'static CStr {
+        let locale = if locale.is_empty() {
+            locale_from_env(CATEGORY_NAMES[category as usize])
+                .unwrap_or_else(|| "C".to_owned())
+                .into_bytes()
+        } else {
+            locale.to_owned()
+        };
+        self.intern(&locale)
+    }
+
+    /// Returns the name of the locale that lookups for `category` use in the calling thread.
+    fn lookup_locale(&self, category: c_int) -> &'static CStr {
+        let thread_locale = THREAD_LOCALE.with(Cell::get);
+        if thread_locale == LC_GLOBAL_LOCALE {
+            self.locales[category as usize]
+        } else {
+            // `uselocale` requires the object to stay alive while it's in use.
+            unsafe { (*(thread_locale as *const LocaleObject)).locales[category as usize] }
+        }
+    }
+
+    /// Returns the name of the locale for `category`. For `LC_ALL`, that's either the name shared
+    /// by all categories, or a list of all categories with their locales, like in glibc.
+    fn locale(&mut self, category: c_int) -> &'static CStr {
+        if category != LC_ALL {
+            return self.locales[category as usize];
+        }
+
+        let first = self.locales[0];
+        let uniform = (0..CATEGORY_NAMES.len())
+            .filter(|&category| category as c_int != LC_ALL)
+            .all(|category| self.locales[category] == first);
+        if uniform {
+            return first;
+        }
+
+        let composite = (0..CATEGORY_NAMES.len())
+            .filter(|&category| category as c_int != LC_ALL)
+            .map(|category| {
+                format!(
+                    "{}={}",
+                    CATEGORY_NAMES[category],
+                    self.locales[category].to_string_lossy()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        self.intern(composite.as_bytes())
+    }
+
+    fn load(&mut self, path: &Path) -> Option<&'static Catalog> {
+        if let Some(catalog) = self.catalogs.get(path) {
+            return *catalog;
+        }
+
+        let catalog = match self.files.get(path) {
+            Some(&data) => Catalog::parse(data).ok(),
+            // Made-up paths of embedded catalogs that weren't registered must not be looked up on
+            // disk, where they would be relative to the current directory.
+            None if embedded::is_under_dirname(path) => None,
+            None => fs::read(path)
+                .ok()
+                .and_then(|data| Catalog::parse(data).ok()),
+        }
+        .map(|catalog| &*Box::leak(Box::new(catalog)));
+        self.catalogs.insert(path.to_owned(), catalog);
+        catalog
+    }
+
+    /// Returns the paths of all the catalogs that might contain the translations for
+    /// `domainname` in `category`, most preferred first.
+    fn catalog_paths(
+        &self,
+        domainname: &[u8],
+        category: c_int,
+        languages: &[String],
+    ) -> Vec<PathBuf> {
+        let binding = self.bindings.get(domainname);
+        if let Some(path) = binding.and_then(|binding| binding.catalogs.get(&category)) {
+            return vec![path.clone()];
+        }
+
+        let mut file_name = domainname.to_owned();
+        file_name.extend_from_slice(b".mo");
+        let file_name = path_from_bytes(&file_name);
+        let dirname = self.dirname(domainname);
+
+        let mut paths = vec![];
+        for language in languages {
+            for variant in locale_variants(language) {
+                paths.push(
+                    dirname
+                        .join(variant)
+                        .join(CATEGORY_NAMES[category as usize])
+                        .join(&file_name),
+                );
+            }
+        }
+        paths
+    }
+
+    fn find(
+        &mut self,
+        domainname: &[u8],
+        category: c_int,
+        msgid: &[u8],
+    ) -> Option<(&'static Catalog, usize)> {
+        let languages = languages(
+            &self.lookup_locale(category).to_string_lossy(),
+            non_empty_var("LANGUAGE"),
+        );
+        if languages.is_empty() {
+            return None;
+        }
+
+        for path in self.catalog_paths(domainname, category, &languages) {
+            if let Some(catalog) = self.load(&path) {
+                if let Some(index) = catalog.find(msgid) {
+                    return Some((catalog, index));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn intern_into(strings: &mut HashMap<Vec<u8>, &'static CStr>, string: &[u8]) -> &'static CStr {
+    if let Some(interned) = strings.get(string) {
+        return interned;
+    }
+
+    let owned = CString::new(string).expect("interned strings can't contain 0 bytes");
+    let interned: &'static CStr = Box::leak(owned.into_boxed_c_str());
+    strings.insert(string.to_owned(), interned);
+    interned
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_owned()
+}
+
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+unsafe fn dcigettext(
+    domainname: *const c_char,
+    msgid1: *const c_char,
+    msgid2: *const c_char,
+    n: c_ulong,
+    category: c_int,
+) -> *mut c_char {
+    if msgid1.is_null() {
+        return ptr::null_mut();
+    }
+
+    let untranslated = if !msgid2.is_null() && n != 1 {
+        msgid2
+    } else {
+        msgid1
+    } as *mut c_char;
+
+    if category < 0 || category as usize >= CATEGORY_NAMES.len() || category == LC_ALL {
+        return untranslated;
+    }
+
+    let mut state = lock();
+    let state = state.get();
+    let domainname = if domainname.is_null() {
+        state.textdomain.to_bytes().to_owned()
+    } else {
+        CStr::from_ptr(domainname).to_bytes().to_owned()
+    };
+
+    match state.find(&domainname, category, CStr::from_ptr(msgid1).to_bytes()) {
+        Some((catalog, index)) => {
+            // `c_ulong` is only 32 bits wide on Windows.
+            #[allow(clippy::useless_conversion)]
+            let form = if msgid2.is_null() {
+                0
+            } else {
+                catalog.plural_form(u64::from(n))
+            };
+            catalog.translation(index, form) as *mut c_char
+        }
+        None => untranslated,
+    }
+}
+
+pub unsafe fn gettext(s: *const c_char) -> *mut c_char {
+    dcigettext(ptr::null(), s, ptr::null(), 0, LC_MESSAGES)
+}
+
+pub unsafe fn dgettext(domain: *const c_char, s: *const c_char) -> *mut c_char {
+    dcigettext(domain, s, ptr::null(), 0, LC_MESSAGES)
+}
+
+pub unsafe fn dcgettext(domain: *const c_char, s: *const c_char, category: c_int) -> *mut c_char {
+    dcigettext(domain, s, ptr::null(), 0, category)
+}
+
+pub unsafe fn ngettext(s1: *const c_char, s2: *const c_char, n: c_ulong) -> *mut c_char {
+    dcigettext(ptr::null(), s1, s2, n, LC_MESSAGES)
+}
+
+pub unsafe fn dngettext(
+    domain: *const c_char,
+    s1: *const c_char,
+    s2: *const c_char,
+    n: c_ulong,
+) -> *mut c_char {
+    dcigettext(domain, s1, s2, n, LC_MESSAGES)
+}
+
+pub unsafe fn dcngettext(
+    domain: *const c_char,
+    s1: *const c_char,
+    s2: *const c_char,
+    n: c_ulong,
+    category: c_int,
+) -> *mut c_char {
+    dcigettext(domain, s1, s2, n, category)
+}
+
+/// Returns the binding for a non-empty domain name, or `None` if the name is empty or `NULL`.
+unsafe fn binding(state: &mut State, domain: *const c_char) -> Option<&mut Binding> {
+    if domain.is_null() || *domain == 0 {
+        return None;
+    }
+
+    let domain = CStr::from_ptr(domain).to_bytes().to_owned();
+    Some(state.bindings.entry(domain).or_default())
+}
+
+pub unsafe fn bindtextdomain(domain: *const c_char, dir: *const c_char) -> *mut c_char {
+    let mut state = lock();
+    let state = state.get();
+    match binding(state, domain) {
+        Some(binding) => {
+            if !dir.is_null() {
+                binding.dirname = Some(path_from_bytes(CStr::from_ptr(dir).to_bytes()));
+                binding.catalogs.clear();
+            }
+        }
+        None => return ptr::null_mut(),
+    }
+
+    let dirname = state.dirname(CStr::from_ptr(domain).to_bytes());
+    state.intern(&path_to_bytes(&dirname)).as_ptr() as *mut c_char
+}
+
+#[cfg(windows)]
+pub unsafe fn wbindtextdomain(domain: *const c_char, dir: *const u16) -> *mut u16 {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+    let mut state = lock();
+    let state = state.get();
+    match binding(state, domain) {
+        Some(binding) => {
+            if !dir.is_null() {
+                let mut len = 0;
+                while *dir.offset(len) != 0 {
+                    len += 1;
+                }
+                let dir = std::slice::from_raw_parts(dir, len as usize);
+                binding.dirname = Some(PathBuf::from(OsString::from_wide(dir)));
+                binding.catalogs.clear();
+            }
+        }
+        None => return ptr::null_mut(),
+    }
+
+    let dirname = state.dirname(CStr::from_ptr(domain).to_bytes());
+    let dirname: Vec<u16> = dirname.as_os_str().encode_wide().collect();
+    state.intern_wide(&dirname).as_ptr() as *mut u16
+}
+
+pub unsafe fn textdomain(domain: *const c_char) -> *mut c_char {
+    let mut state = lock();
+    let state = state.get();
+    if !domain.is_null() {
+        let domain = CStr::from_ptr(domain).to_bytes();
+        state.textdomain = if domain.is_empty() {
+            state.intern(DEFAULT_DOMAIN)
+        } else {
+            state.intern(domain)
+        };
+    }
+
+    state.textdomain.as_ptr() as *mut c_char
+}
+
+pub unsafe fn bind_textdomain_codeset(
+    domain: *const c_char,
+    codeset: *const c_char,
+) -> *mut c_char {
+    let mut state = lock();
+    let state = state.get();
+    let codeset = if codeset.is_null() {
+        None
+    } else {
+        Some(state.intern(CStr::from_ptr(codeset).to_bytes()))
+    };
+
+    match binding(state, domain) {
+        Some(binding) => {
+            if codeset.is_some() {
+                binding.codeset = codeset;
+            }
+            binding
+                .codeset
+                .map_or(ptr::null_mut(), |codeset| codeset.as_ptr() as *mut c_char)
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+pub unsafe fn setlocale(category: c_int, locale: *const c_char) -> *mut c_char {
+    if category < 0 || category as usize >= CATEGORY_NAMES.len() {
+        return ptr::null_mut();
+    }
+
+    let mut state = lock();
+    let state = state.get();
+    if !locale.is_null() {
+        let locale = CStr::from_ptr(locale).to_bytes();
+        if category == LC_ALL && locale.contains(&b'=') {
+            // A list of categories with their locales, as returned for `LC_ALL`.
+            let mut locales = vec![];
+            for entry in locale.split(|&byte| byte == b';') {
+                let mut parts = entry.splitn(2, |&byte| byte == b'=');
+                let name = parts.next().unwrap_or_default();
+                let other = CATEGORY_NAMES
+                    .iter()
+                    .position(|category| category.as_bytes() == name);
+                match (other, parts.next()) {
+                    (Some(other), Some(value)) if other as c_int != LC_ALL => {
+                        locales.push((other as c_int, value))
+                    }
+                    _ => return ptr::null_mut(),
+                }
+            }
+            for (other, value) in locales {
+                state.set_locale(other, value);
+            }
+        } else if category == LC_ALL {
+            for other in 0..CATEGORY_NAMES.len() as c_int {
+                if other != LC_ALL {
+                    state.set_locale(other, locale);
+                }
+            }
+        } else {
+            state.set_locale(category, locale);
+        }
+    }
+
+    state.locale(category).as_ptr() as *mut c_char
+}
+
+pub unsafe fn newlocale(category_mask: c_int, locale: *const c_char, base: locale_t) -> locale_t {
+    if locale.is_null() || base == LC_GLOBAL_LOCALE {
+        return ptr::null_mut();
+    }
+
+    let mut state = lock();
+    let state = state.get();
+    let mut object = if base.is_null() {
+        let c = state.intern(b"C");
+        Box::new(LocaleObject { locales: [c; 13] })
+    } else {
+        // Like in glibc, `base` is reused for the result.
+        Box::from_raw(base as *mut LocaleObject)
+    };
+    let locale = CStr::from_ptr(locale).to_bytes();
+    for category in 0..CATEGORY_NAMES.len() as c_int {
+        if category != LC_ALL && category_mask & (1 << category) != 0 {
+            object.locales[category as usize] = state.resolve_locale(category, locale);
+        }
+    }
+    Box::into_raw(object) as locale_t
+}
+
+pub unsafe fn uselocale(locale: locale_t) -> locale_t {
+    THREAD_LOCALE.with(|thread_locale| {
+        if locale.is_null() {
+            thread_locale.get()
+        } else {
+            thread_locale.replace(locale)
+        }
+    })
+}
+
+pub unsafe fn freelocale(locale: locale_t) {
+    drop(Box::from_raw(locale as *mut LocaleObject));
+}
+
+/// Makes lookups in `domainname` for `category` use the given MO file, rather than search for one
+/// in the directory set by `bindtextdomain`.
+///
+/// This is used by [`TextDomain`][::TextDomain], which already knows which file it wants.
+pub fn bind_catalog(domainname: &[u8], category: c_int, path: &Path) {
+    let mut state = lock();
+    state
+        .get()
+        .bindings
+        .entry(domainname.to_owned())
+        .or_default()
+        .catalogs
+        .insert(category, path.to_owned());
+}
+
+/// Makes the backend read the MO file at `path` from `data` rather than from the filesystem. The
+/// file doesn't have to exist.
+///
+/// This is used by the [`embedded`][::embedded] module.
+pub fn register_file(path: &Path, data: &'static [u8]) {
+    let mut state = lock();
+    let state = state.get();
+    state.files.insert(path.to_owned(), data);
+    state.catalogs.remove(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::catalog::tests::mo_file;
+    use super::*;
+
+    use std::ffi::CString;
+    use test_utils::TempDir;
+
+    fn lookup(domainname: &str, msgid: &str, msgid_plural: Option<&str>, n: c_ulong) -> String {
+        let domainname = CString::new(domainname).unwrap();
+        let msgid = CString::new(msgid).unwrap();
+        let msgid_plural = msgid_plural.map(|plural| CString::new(plural).unwrap());
+        unsafe {
+            let result = dcigettext(
+                domainname.as_ptr(),
+                msgid.as_ptr(),
+                msgid_plural
+                    .as_ref()
+                    .map_or(ptr::null(), |plural| plural.as_ptr()),
+                n,
+                LC_MESSAGES,
+            );
+            CStr::from_ptr(result).to_string_lossy().into_owned()
+        }
+    }
+
+    #[test]
+    fn catalogs_are_searched_in_bound_directory() {
+        let mut state = State::new();
+        state
+            .bindings
+            .entry(b"domain".to_vec())
+            .or_default()
+            .dirname = Some(PathBuf::from("/opt/locale"));
+
+        assert_eq!(
+            state.catalog_paths(
+                b"domain",
+                LC_MESSAGES,
+                &["fr_FR".to_owned(), "es".to_owned()]
+            ),
+            vec![
+                PathBuf::from("/opt/locale/fr_FR/LC_MESSAGES/domain.mo"),
+                PathBuf::from("/opt/locale/fr/LC_MESSAGES/domain.mo"),
+                PathBuf::from("/opt/locale/es/LC_MESSAGES/domain.mo"),
+            ]
+        );
+        assert_eq!(
+            state.catalog_paths(b"other", 2, &["es".to_owned()]),
+            vec![PathBuf::from("/usr/share/locale/es/LC_TIME/other.mo")]
+        );
+    }
+
+    #[test]
+    fn translations_are_looked_up_in_bound_catalogs() {
+        let temp_dir = TempDir::new("backend");
+        let dir = temp_dir.path();
+        let path = dir.join("backend_test.mo");
+        fs::write(
+            &path,
+            mo_file(
+                &[
+                    ("Hello, world!", "Bonjour, le monde !"),
+                    ("One file\0Many files", "Un fichier\0Des fichiers"),
+                ],
+                false,
+                true,
+            ),
+        )
+        .unwrap();
+
+        // Other tests change the locale too, but never to "C", so the catalog is always used.
+        let locale = CString::new("fr_FR").unwrap();
+        unsafe {
+            setlocale(LC_MESSAGES, locale.as_ptr());
+        }
+        bind_catalog(b"backend_test", LC_MESSAGES, &path);
+
+        assert_eq!(
+            lookup("backend_test", "Hello, world!", None, 0),
+            "Bonjour, le monde !"
+        );
+        assert_eq!(
+            lookup("backend_test", "One file", Some("Many files"), 1),
+            "Un fichier"
+        );
+        assert_eq!(
+            lookup("backend_test", "One file", Some("Many files"), 3),
+            "Des fichiers"
+        );
+        assert_eq!(
+            lookup("backend_test", "Untranslated", None, 0),
+            "Untranslated"
+        );
+        assert_eq!(
+            lookup(
+                "backend_test",
+                "Untranslated",
+                Some("Untranslated plural"),
+                2
+            ),
+            "Untranslated plural"
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! Translation functions that avoid allocations.
+//!
+//! The functions at the crate root copy their arguments into new `CString`s, and copy the result
+//! into a new `String`. That's convenient, but can get expensive on hot paths that translate lots
+//! of strings. The functions in this module return the string that libintl returned, without
+//! copying it:
+//!
+//! - functions that take `&'static CStr` (like the C strings that [`gettext!`][::gettext!] and
+//!   similar macros build from literals) return `&'static str`. The arguments are passed to
+//!   libintl as they are, and the result is either one of them or a string from the catalog;
+//! - functions with the `_cstr` suffix take any `&CStr`. They return `Cow::Borrowed` if a
+//!   translation was found, and `Cow::Owned` with a copy of the `msgid` otherwise.
+//!
+//! ```rust,no_run
+//! use gettextrs::borrowed;
+//!
+//! use std::ffi::CStr;
+//!
+//! let name = CStr::from_bytes_with_nul(b"Name\0").unwrap();
+//! for _ in 0..1000 {
+//!     let header: &'static str = borrowed::gettext(name);
+//!     println!("{}", header);
+//! }
+//! ```
+//!
+//! # When the results change
+//!
+//! Translations are looked up anew on every call, so calls to [`setlocale`][::setlocale],
+//! [`textdomain`][::textdomain], [`bindtextdomain`][::bindtextdomain] and
+//! [`bind_textdomain_codeset`][::bind_textdomain_codeset] affect all subsequent calls, just like
+//! with other functions.
+//!
+//! Strings returned earlier are **not** invalidated by these calls: libintl keeps the catalogs it
+//! loaded, and the strings converted to other codesets, until the program exits, which is why it
+//! can return them as `const char*` that callers must not free. They just keep the old translation,
+//! so if you store them (e.g. in a cache of rendered table headers), throw them away after
+//! changing the locale or the domain bindings.
+//!
+//! # Contexts
+//!
+//! [`pgettext`] and [`npgettext`] take the context and `msgid` joined by a `\x04` byte, the way
+//! they're stored in MO files, so that no string has to be built at runtime. The
+//! [`pgettext!`][::pgettext!] macro builds it from literals.
+//!
+//! [`pgettext`]: fn.pgettext.html
+//! [`npgettext`]: fn.npgettext.html
+//!
+//! # Panics
+//!
+//! Like their counterparts at the crate root, these functions panic if the result is not in UTF-8
+//! (see [this note](../index.html#utf-8-is-required)).
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+
+use super::{LocaleCategory, PluralCount};
+use ffi;
+
+/// Converts the result of a libintl call into a string, or returns `None` if it's one of the
+/// `msgids` (i.e. the message is not translated).
+///
+/// # Safety
+///
+/// `result` must be a pointer returned by libintl.
+unsafe fn translation(
+    function: &str,
+    msgids: &[*const c_char],
+    result: *const c_char,
+) -> Option<&'static str> {
+    if msgids.contains(&result) {
+        return None;
+    }
+
+    let translation = CStr::from_ptr(result)
+        .to_str()
+        .unwrap_or_else(|_| panic!("{}() returned invalid UTF-8", function));
+    Some(translation)
+}
+
+/// Returns the translation, or `msgid` if `result` points to it.
+///
+/// # Safety
+///
+/// `result` must be a pointer returned by libintl.
+unsafe fn translation_or(
+    function: &str,
+    msgid: &'static CStr,
+    result: *const c_char,
+) -> &'static str {
+    translation(function, &[msgid.as_ptr()], result).unwrap_or_else(|| {
+        msgid
+            .to_str()
+            .unwrap_or_else(|_| panic!("{}() returned invalid UTF-8", function))
+    })
+}
+
+/// Translate msgid to localized message from the default domain.
+///
+/// This is a version of [`gettext`][::gettext()] that doesn't allocate (see [module
+/// documentation](index.html)).
+pub fn gettext(msgid: &'static CStr) -> &'static str {
+    dcgettext_static(None, msgid, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain.
+///
+/// This is a version of [`dgettext`][::dgettext] that doesn't allocate (see [module
+/// documentation](index.html)).
+pub fn dgettext(domainname: &CStr, msgid: &'static CStr) -> &'static str {
+    dcgettext_static(Some(domainname), msgid, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category.
+///
+/// This is a version of [`dcgettext`][::dcgettext] that doesn't allocate (see [module
+/// documentation](index.html)).
+pub fn dcgettext(
+    domainname: &CStr,
+    msgid: &'static CStr,
+    category: LocaleCategory,
+) -> &'static str {
+    dcgettext_static(Some(domainname), msgid, category)
+}
+
+/// Translate msgid to localized message from the default domain (with plural support).
+///
+/// This is a version of [`ngettext`][::ngettext()] that doesn't allocate (see [module
+/// documentation](index.html)).
+pub fn ngettext<N>(msgid: &'static CStr, msgid_plural: &'static CStr, n: N) -> &'static str
+where
+    N: PluralCount,
+{
+    dcngettext_static(None, msgid, msgid_plural, n, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain (with plural support).
+///
+/// This is a version of [`dngettext`][::dngettext] that doesn't allocate (see [module
+/// documentation](index.html)).
+pub fn dngettext<N>(
+    domainname: &CStr,
+    msgid: &'static CStr,
+    msgid_plural: &'static CStr,
+    n: N,
+) -> &'static str
+where
+    N: PluralCount,
+{
+    dcngettext_static(
+        Some(domainname),
+        msgid,
+        msgid_plural,
+        n,
+        LocaleCategory::LcMessages,
+    )
+}
+
+/// Translate msgid to localized message from the default domain (with context support).
+///
+/// This is a version of [`pgettext`][::pgettext()] that doesn't allocate (see [module
+/// documentation](index.html)). `context_id` is the context and `msgid` joined by `\x04`.
+pub fn pgettext(context_id: &'static CStr, msgid: &'static CStr) -> &'static str {
+    unsafe {
+        let result = ffi::dcgettext(
+            ptr::null(),
+            context_id.as_ptr(),
+            LocaleCategory::LcMessages as i32,
+        );
+        translation("dcgettext", &[context_id.as_ptr()], result).unwrap_or_else(|| gettext(msgid))
+    }
+}
+
+/// Translate msgid to localized message from the default domain (with plural support and context
+/// support).
+///
+/// This is a version of [`npgettext`][::npgettext] that doesn't allocate (see [module
+/// documentation](index.html)). `context_id` is the context and `msgid` joined by `\x04`.
+pub fn npgettext<N>(
+    context_id: &'static CStr,
+    msgid: &'static CStr,
+    msgid_plural: &'static CStr,
+    n: N,
+) -> &'static str
+where
+    N: PluralCount,
+{
+    unsafe {
+        let result = ffi::dcngettext(
+            ptr::null(),
+            context_id.as_ptr(),
+            msgid_plural.as_ptr(),
+            n.to_c_ulong(),
+            LocaleCategory::LcMessages as i32,
+        );
+        translation(
+            "dcngettext",
+            &[context_id.as_ptr(), msgid_plural.as_ptr()],
+            result,
+        )
+        .unwrap_or_else(|| ngettext(msgid, msgid_plural, n))
+    }
+}
+
+fn dcgettext_static(
+    domainname: Option<&CStr>,
+    msgid: &'static CStr,
+    category: LocaleCategory,
+) -> &'static str {
+    unsafe {
+        let result = ffi::dcgettext(
+            domainname.map_or(ptr::null(), CStr::as_ptr),
+            msgid.as_ptr(),
+            category as i32,
+        );
+        translation_or("dcgettext", msgid, result)
+    }
+}
+
+fn dcngettext_static<N>(
+    domainname: Option<&CStr>,
+    msgid: &'static CStr,
+    msgid_plural: &'static CStr,
+    n: N,
+    category: LocaleCategory,
+) -> &'static str
+where
+    N: PluralCount,
+{
+    unsafe {
+        let result = ffi::dcngettext(
+            domainname.map_or(ptr::null(), CStr::as_ptr),
+            msgid.as_ptr(),
+            msgid_plural.as_ptr(),
+            n.to_c_ulong(),
+            category as i32,
+        );
+        if ptr::eq(result, msgid_plural.as_ptr()) {
+            return translation_or("dcngettext", msgid_plural, result);
+        }
+        translation_or("dcngettext", msgid, result)
+    }
+}
+
+/// Translate msgid to localized message from the default domain.
+///
+/// This is a version of [`gettext`][::gettext()] that doesn't allocate if the translation is found
+/// (see [module documentation](index.html)).
+pub fn gettext_cstr(msgid: &CStr) -> Cow<'static, str> {
+    dcgettext_cstr_impl(None, msgid, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain.
+///
+/// This is a version of [`dgettext`][::dgettext] that doesn't allocate if the translation is
+/// found (see [module documentation](index.html)).
+pub fn dgettext_cstr(domainname: &CStr, msgid: &CStr) -> Cow<'static, str> {
+    dcgettext_cstr_impl(Some(domainname), msgid, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category.
+///
+/// This is a version of [`dcgettext`][::dcgettext] that doesn't allocate if the translation is
+/// found (see [module documentation](index.html)).
+pub fn dcgettext_cstr(
+    domainname: &CStr,
+    msgid: &CStr,
+    category: LocaleCategory,
+) -> Cow<'static, str> {
+    dcgettext_cstr_impl(Some(domainname), msgid, category)
+}
+
+/// Translate msgid to localized message from the default domain (with plural support).
+///
+/// This is a version of [`ngettext`][::ngettext()] that doesn't allocate if the translation is
+/// found (see [module documentation](index.html)).
+pub fn ngettext_cstr<N>(msgid: &CStr, msgid_plural: &CStr, n: N) -> Cow<'static, str>
+where
+    N: PluralCount,
+{
+    dcngettext_cstr_impl(None, msgid, msgid_plural, n, LocaleCategory::LcMessages)
+}
+
+/// Translate msgid to localized message from the specified domain (with plural support).
+///
+/// This is a version of [`dngettext`][::dngettext] that doesn't allocate if the translation is
+/// found (see [module documentation](index.html)).
+pub fn dngettext_cstr<N>(
+    domainname: &CStr,
+    msgid: &CStr,
+    msgid_plural: &CStr,
+    n: N,
+) -> Cow<'static, str>
+where
+    N: PluralCount,
+{
+    dcngettext_cstr_impl(
+        Some(domainname),
+        msgid,
+        msgid_plural,
+        n,
+        LocaleCategory::LcMessages,
+    )
+}
+
+fn untranslated(msgid: &CStr) -> Cow<'static, str> {
+    Cow::Owned(msgid.to_str().expect("`msgid` is not in UTF-8").to_owned())
+}
+
+fn dcgettext_cstr_impl(
+    domainname: Option<&CStr>,
+    msgid: &CStr,
+    category: LocaleCategory,
+) -> Cow<'static, str> {
+    unsafe {
+        let result = ffi::dcgettext(
+            domainname.map_or(ptr::null(), CStr::as_ptr),
+            msgid.as_ptr(),
+            category as i32,
+        );
+        match translation("dcgettext", &[msgid.as_ptr()], result) {
+            Some(translation) => Cow::Borrowed(translation),
+            None => untranslated(msgid),
+        }
+    }
+}
+
+fn dcngettext_cstr_impl<N>(
+    domainname: Option<&CStr>,
+    msgid: &CStr,
+    msgid_plural: &CStr,
+    n: N,
+    category: LocaleCategory,
+) -> Cow<'static, str>
+where
+    N: PluralCount,
+{
+    unsafe {
+        let result = ffi::dcngettext(
+            domainname.map_or(ptr::null(), CStr::as_ptr),
+            msgid.as_ptr(),
+            msgid_plural.as_ptr(),
+            n.to_c_ulong(),
+            category as i32,
+        );
+        if ptr::eq(result, msgid_plural.as_ptr()) {
+            return untranslated(msgid_plural);
+        }
+        match translation("dcngettext", &[msgid.as_ptr()], result) {
+            Some(translation) => Cow::Borrowed(translation),
+            None => untranslated(msgid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untranslated_strings_are_returned_as_is() {
+        let msgid = CStr::from_bytes_with_nul(b"This message is not translated\0").unwrap();
+        let plural = CStr::from_bytes_with_nul(b"These messages are not translated\0").unwrap();
+        let context_id =
+            CStr::from_bytes_with_nul(b"context\x04This message is not translated\0").unwrap();
+        let domainname = CStr::from_bytes_with_nul(b"some_domain\0").unwrap();
+        assert_eq!(gettext(msgid).as_ptr(), msgid.as_ptr() as *const u8);
+        assert_eq!(
+            dgettext(domainname, msgid).as_ptr(),
+            msgid.as_ptr() as *const u8
+        );
+        assert_eq!(
+            pgettext(context_id, msgid).as_ptr(),
+            msgid.as_ptr() as *const u8
+        );
+
+        assert_eq!(
+            ngettext(msgid, plural, 1).as_ptr(),
+            msgid.as_ptr() as *const u8
+        );
+        assert_eq!(
+            ngettext(msgid, plural, 2).as_ptr(),
+            plural.as_ptr() as *const u8
+        );
+        assert_eq!(
+            npgettext(context_id, msgid, plural, 2u64).as_ptr(),
+            plural.as_ptr() as *const u8
+        );
+
+        let msgid = CStr::from_bytes_with_nul(b"Not translated\0").unwrap();
+        let plural = CStr::from_bytes_with_nul(b"Not translated either\0").unwrap();
+        assert_eq!(
+            gettext_cstr(msgid),
+            Cow::Owned::<str>("Not translated".to_owned())
+        );
+        assert_eq!(ngettext_cstr(msgid, plural, 5), "Not translated either");
+    }
+}
@@ -0,0 +1,315 @@
+//! A handle for looking up messages in a particular text domain.
+
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::path::PathBuf;
+
+use super::{
+    bind_textdomain_codeset, bindtextdomain, build_context_id, panic_on_zero_in_ctxt, textdomain,
+    LocaleCategory, PluralCount, CONTEXT_SEPARATOR,
+};
+use ffi;
+
+/// A text domain, for looking up messages in it regardless of the default domain set with
+/// [`textdomain`].
+///
+/// The default text domain belongs to the application, so libraries shouldn't rely on it, nor
+/// change it. Instead, a library can bind its domain with [`TextDomain::library_mode`] and look
+/// its messages up through a `Domain`:
+///
+/// ```no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// use gettextrs::{Domain, TextDomain};
+///
+/// static DOMAIN: Domain = domain!("my_library");
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// TextDomain::new("my_library").library_mode().init()?;
+///
+/// println!("{}", DOMAIN.gettext("Hello, world!"));
+/// println!("{}", DOMAIN.ngettext("One file", "Many files", 3));
+/// println!("{}", DOMAIN.pgettext("menu", "Open"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The name of the domain is checked and turned into a C string once, when the `Domain` is
+/// created, so the methods only have to convert the messages. They mirror the functions at the
+/// crate root that take a `domainname`; [`setlocale`] has no counterpart, as it doesn't depend on
+/// the domain.
+///
+/// Messages are looked up in the [`LocaleCategory::LcMessages`] category, unless another one is
+/// set with [`with_category`] or passed to [`domain!`].
+///
+/// [`textdomain`]: fn.textdomain.html
+/// [`setlocale`]: fn.setlocale.html
+/// [`TextDomain::library_mode`]: struct.TextDomain.html#method.library_mode
+/// [`LocaleCategory::LcMessages`]: enum.LocaleCategory.html#variant.LcMessages
+/// [`with_category`]: struct.Domain.html#method.with_category
+/// [`domain!`]: macro.domain.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Domain {
+    name: Cow<'static, CStr>,
+    category: Option<LocaleCategory>,
+}
+
+impl Domain {
+    /// Creates a handle for the text domain `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains an internal 0 byte, as such values can't be passed to the
+    /// underlying C API.
+    pub fn new<T: Into<Vec<u8>>>(name: T) -> Domain {
+        let name = CString::new(name).expect("`domainname` contains an internal 0 byte");
+        Domain {
+            name: Cow::Owned(name),
+            category: None,
+        }
+    }
+
+    /// Creates a handle for the text domain `name` at compile time, e.g. to store it in a
+    /// `static`. The [`domain!`] macro calls this with a string literal.
+    ///
+    /// [`domain!`]: macro.domain.html
+    pub const fn from_static(name: &'static CStr, category: Option<LocaleCategory>) -> Domain {
+        Domain {
+            name: Cow::Borrowed(name),
+            category,
+        }
+    }
+
+    /// Look messages up in the given locale category instead of
+    /// [`LocaleCategory::LcMessages`], like [`dcgettext`] does.
+    ///
+    /// [`LocaleCategory::LcMessages`]: enum.LocaleCategory.html#variant.LcMessages
+    /// [`dcgettext`]: fn.dcgettext.html
+    pub fn with_category(mut self, category: LocaleCategory) -> Domain {
+        self.category = Some(category);
+        self
+    }
+
+    /// Returns the name of the text domain.
+    pub fn name(&self) -> &CStr {
+        &self.name
+    }
+
+    /// Returns the locale category set with [`with_category`], if any.
+    ///
+    /// [`with_category`]: struct.Domain.html#method.with_category
+    pub fn category(&self) -> Option<LocaleCategory> {
+        self.category
+    }
+
+    fn category_or_default(&self) -> LocaleCategory {
+        self.category.unwrap_or(LocaleCategory::LcMessages)
+    }
+
+    /// Translate msgid to localized message from this domain. See [`dgettext`] and
+    /// [`dcgettext`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `msgid` contains an internal 0 byte, as such values can't be passed to the underlying C
+    ///   API;
+    /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+    ///
+    /// [`dgettext`]: fn.dgettext.html
+    /// [`dcgettext`]: fn.dcgettext.html
+    pub fn gettext<T: Into<String>>(&self, msgid: T) -> String {
+        let msgid = CString::new(msgid.into()).expect("`msgid` contains an internal 0 byte");
+        unsafe {
+            CStr::from_ptr(ffi::dcgettext(
+                self.name.as_ptr(),
+                msgid.as_ptr(),
+                self.category_or_default() as i32,
+            ))
+            .to_str()
+            .expect("dcgettext() returned invalid UTF-8")
+            .to_owned()
+        }
+    }
+
+    /// Translate msgid to localized message from this domain (with plural support). See
+    /// [`dngettext`] and [`dcngettext`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `msgid` or `msgid_plural` contain an internal 0 byte, as such values can't be passed to
+    ///   the underlying C API;
+    /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+    ///
+    /// [`dngettext`]: fn.dngettext.html
+    /// [`dcngettext`]: fn.dcngettext.html
+    pub fn ngettext<T, S, N>(&self, msgid: T, msgid_plural: S, n: N) -> String
+    where
+        T: Into<String>,
+        S: Into<String>,
+        N: PluralCount,
+    {
+        let msgid = CString::new(msgid.into()).expect("`msgid` contains an internal 0 byte");
+        let msgid_plural =
+            CString::new(msgid_plural.into()).expect("`msgid_plural` contains an internal 0 byte");
+        unsafe {
+            CStr::from_ptr(ffi::dcngettext(
+                self.name.as_ptr(),
+                msgid.as_ptr(),
+                msgid_plural.as_ptr(),
+                n.to_c_ulong(),
+                self.category_or_default() as i32,
+            ))
+            .to_str()
+            .expect("dcngettext() returned invalid UTF-8")
+            .to_owned()
+        }
+    }
+
+    /// Translate msgid to localized message from this domain (with context support). See
+    /// [`dpgettext`] and [`dcpgettext`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `msgctxt` or `msgid` contain an internal 0 byte, as such values can't be passed to the
+    ///   underlying C API;
+    /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+    ///
+    /// [`dpgettext`]: fn.dpgettext.html
+    /// [`dcpgettext`]: fn.dcpgettext.html
+    pub fn pgettext<T, U>(&self, msgctxt: T, msgid: U) -> String
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let msgctxt = msgctxt.into();
+        panic_on_zero_in_ctxt(&msgctxt);
+
+        let msgid = msgid.into();
+        let text = build_context_id(&msgctxt, &msgid);
+
+        let translation = self.gettext(text);
+        if translation.contains(CONTEXT_SEPARATOR) {
+            return self.gettext(msgid);
+        }
+
+        translation
+    }
+
+    /// Translate msgid to localized message from this domain (with plural support and context
+    /// support). See [`dnpgettext`] and [`dcnpgettext`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `msgctxt`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such values can't
+    ///   be passed to the underlying C API;
+    /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+    ///
+    /// [`dnpgettext`]: fn.dnpgettext.html
+    /// [`dcnpgettext`]: fn.dcnpgettext.html
+    pub fn npgettext<T, U, V, N>(&self, msgctxt: T, msgid: U, msgid_plural: V, n: N) -> String
+    where
+        T: Into<String>,
+        U: Into<String>,
+        V: Into<String>,
+        N: PluralCount,
+    {
+        let msgctxt = msgctxt.into();
+        panic_on_zero_in_ctxt(&msgctxt);
+
+        let singular_msgid = msgid.into();
+        let plural_msgid = msgid_plural.into();
+        let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+        let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+        let translation = self.ngettext(singular_ctxt, plural_ctxt, n);
+        if translation.contains(CONTEXT_SEPARATOR) {
+            return self.ngettext(singular_msgid, plural_msgid, n);
+        }
+
+        translation
+    }
+
+    /// Make this domain the default one. See [`textdomain`][::textdomain()].
+    pub fn textdomain(&self) -> Result<Vec<u8>, io::Error> {
+        textdomain(self.name.to_bytes())
+    }
+
+    /// Specify the directory that contains MO files for this domain. See
+    /// [`bindtextdomain`][::bindtextdomain()].
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `dirname` contains an internal 0 byte, as such values can't be passed to the underlying
+    ///   C API;
+    /// * on targets other than Unix and Windows, `dirname` is not in UTF-8.
+    pub fn bindtextdomain<U: Into<PathBuf>>(&self, dirname: U) -> Result<PathBuf, io::Error> {
+        bindtextdomain(self.name.to_bytes(), dirname)
+    }
+
+    /// Set encoding of messages translated from this domain. See
+    /// [`bind_textdomain_codeset`][::bind_textdomain_codeset()].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `codeset` contains an internal 0 byte, as such values can't be passed to the
+    /// underlying C API.
+    pub fn bind_textdomain_codeset<U: Into<String>>(
+        &self,
+        codeset: U,
+    ) -> Result<Option<String>, io::Error> {
+        bind_textdomain_codeset(self.name.to_bytes(), codeset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DOMAIN: Domain = domain!("static_domain");
+    static TIME_DOMAIN: Domain = domain!("static_domain", LocaleCategory::LcTime);
+
+    #[test]
+    fn domains_are_created() {
+        assert_eq!(DOMAIN.name().to_bytes(), b"static_domain");
+        assert_eq!(DOMAIN.category(), None);
+        assert_eq!(TIME_DOMAIN.category(), Some(LocaleCategory::LcTime));
+        assert_eq!(Domain::new("static_domain"), DOMAIN);
+        assert_eq!(
+            DOMAIN.clone().with_category(LocaleCategory::LcTime),
+            TIME_DOMAIN
+        );
+    }
+
+    #[test]
+    fn untranslated_messages_are_returned_as_is() {
+        assert_eq!(DOMAIN.gettext("Not translated"), "Not translated");
+        assert_eq!(TIME_DOMAIN.gettext("Not translated"), "Not translated");
+        assert_eq!(DOMAIN.ngettext("One file", "Many files", 2), "Many files");
+        assert_eq!(
+            DOMAIN.pgettext("context", "Not translated"),
+            "Not translated"
+        );
+        assert_eq!(
+            TIME_DOMAIN.npgettext("context", "One file", "Many files", 1),
+            "One file"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`domainname` contains an internal 0 byte")]
+    fn new_panics_on_zero_in_name() {
+        Domain::new("hello\0world");
+    }
+}
@@ -0,0 +1,249 @@
+//! Translation catalogs embedded in the program.
+//!
+//! Programs that are distributed as a single file can't rely on MO files being installed next to
+//! them or in system directories. Instead, the catalogs can be compiled into the program with
+//! `include_bytes!` and registered at startup; [`gettext`][::gettext()],
+//! [`ngettext`][::ngettext()] and other functions then find translations in them as if they were
+//! files.
+//!
+//! This module is only available with the `pure-rust` feature, as libintl can only read catalogs
+//! from files.
+//!
+//! # Embedding a `locale` directory
+//!
+//! The easiest way to embed catalogs is to have the build script of your crate list them, using
+//! `gettext_tools::build::embed` from the gettext-tools crate. It writes a file into `OUT_DIR`
+//! that [`include_catalogs!`] then includes:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! extern crate gettext_tools;
+//!
+//! fn main() {
+//!     gettext_tools::build::embed("locale").unwrap();
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // main.rs
+//! #[macro_use]
+//! extern crate gettextrs;
+//!
+//! use gettextrs::*;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     TextDomain::new("hellorust")
+//!         .embedded(include_catalogs!())
+//!         .init()?;
+//!     println!("{}", gettext("Hello, world!"));
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`TextDomain::embedded`][::TextDomain::embedded] searches the embedded catalogs for the user's
+//! language the same way it searches directories. Without `TextDomain`, call [`register`] and then
+//! set up the locale and the domain as usual.
+//!
+//! # Embedding catalogs by hand
+//!
+//! Catalogs can also be listed explicitly. The paths follow the layout of a `locale` directory,
+//! i.e. `language/LC_MESSAGES/domain.mo`:
+//!
+//! ```rust,ignore
+//! use gettextrs::embedded::{self, EmbeddedCatalog};
+//! use gettextrs::*;
+//!
+//! static CATALOGS: &[EmbeddedCatalog] = &[EmbeddedCatalog {
+//!     path: "fr/LC_MESSAGES/hellorust.mo",
+//!     data: include_bytes!("../locale/fr/LC_MESSAGES/hellorust.mo"),
+//! }];
+//!
+//! embedded::register(CATALOGS);
+//! setlocale(LocaleCategory::LcAll, "");
+//! textdomain("hellorust").unwrap();
+//! ```
+//!
+//! [`include_catalogs!`]: ../macro.include_catalogs.html
+
+use std::path::{Path, PathBuf};
+
+use super::bindtextdomain;
+use ffi;
+
+/// The directory that embedded catalogs appear to be in. [`register`] binds domains to it, so
+/// that's what [`domain_directory`][::getters::domain_directory] returns for them.
+pub const DIRNAME: &str = "<embedded>";
+
+/// An MO file embedded in the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedCatalog {
+    /// Path of the file relative to the `locale` directory, e.g. `fr/LC_MESSAGES/hellorust.mo`.
+    /// Separators are always `/`.
+    pub path: &'static str,
+    /// Contents of the file.
+    pub data: &'static [u8],
+}
+
+impl EmbeddedCatalog {
+    /// Returns the language part of the path, e.g. `fr` for `fr/LC_MESSAGES/hellorust.mo`.
+    pub fn language(&self) -> &'static str {
+        self.path.split('/').next().unwrap_or("")
+    }
+
+    /// Returns the domain of the catalog, i.e. the file name without the `.mo` extension.
+    pub fn domain(&self) -> Option<&'static str> {
+        self.path.rsplit('/').next()?.strip_suffix(".mo")
+    }
+
+    /// Returns the path under [`DIRNAME`] at which the catalog is registered.
+    fn registered_path(&self) -> PathBuf {
+        self.path
+            .split('/')
+            .fold(PathBuf::from(DIRNAME), |path, component| {
+                path.join(component)
+            })
+    }
+}
+
+/// Registers the catalogs and binds their domains to [`DIRNAME`], so that translations are looked
+/// up in them. Catalogs registered earlier with the same path are replaced.
+///
+/// Lookups in the bound domains no longer search the filesystem; call
+/// [`bindtextdomain`][::bindtextdomain()] to bind a domain to a directory again.
+///
+/// # Panics
+///
+/// Panics if a domain name contains an internal 0 byte.
+pub fn register(catalogs: &[EmbeddedCatalog]) {
+    mount(catalogs);
+
+    let mut domains = catalogs
+        .iter()
+        .filter_map(EmbeddedCatalog::domain)
+        .collect::<Vec<_>>();
+    domains.sort_unstable();
+    domains.dedup();
+    for domain in domains {
+        bindtextdomain(domain, DIRNAME).expect("the pure-Rust backend doesn't fail to bind");
+    }
+}
+
+/// Registers the catalogs with the backend without binding any domains.
+pub(crate) fn mount(catalogs: &[EmbeddedCatalog]) {
+    for catalog in catalogs {
+        ffi::register_file(&catalog.registered_path(), catalog.data);
+    }
+}
+
+/// Finds a catalog for `domainname` and `category` (e.g. `LC_MESSAGES`) in the directory for
+/// `language`, and returns the path at which it's registered. Like for directories,
+/// [`TextDomain`][::TextDomain] tries the variants of the language name in turn.
+pub(crate) fn find(
+    catalogs: &[EmbeddedCatalog],
+    language: &str,
+    category: &str,
+    domainname: &str,
+) -> Option<PathBuf> {
+    catalogs
+        .iter()
+        .find(|catalog| {
+            let mut components = catalog.path.split('/');
+            components.next() == Some(language)
+                && components.next() == Some(category)
+                && catalog.domain() == Some(domainname)
+                && components.count() == 1
+        })
+        .map(EmbeddedCatalog::registered_path)
+}
+
+/// Returns `true` if `path` is [`DIRNAME`], i.e. stands for the embedded catalogs.
+pub(crate) fn is_dirname(path: &Path) -> bool {
+    path == Path::new(DIRNAME)
+}
+
+/// Returns `true` if `path` is inside [`DIRNAME`], i.e. can only name an embedded catalog.
+pub(crate) fn is_under_dirname(path: &Path) -> bool {
+    path.starts_with(DIRNAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATALOGS: &[EmbeddedCatalog] = &[
+        EmbeddedCatalog {
+            path: "fr_FR/LC_MESSAGES/hello.mo",
+            data: b"",
+        },
+        EmbeddedCatalog {
+            path: "de/LC_TIME/hello.mo",
+            data: b"",
+        },
+        EmbeddedCatalog {
+            path: "de/LC_MESSAGES/other.mo",
+            data: b"",
+        },
+    ];
+
+    #[test]
+    fn paths_are_split() {
+        assert_eq!(CATALOGS[0].language(), "fr_FR");
+        assert_eq!(CATALOGS[0].domain(), Some("hello"));
+        assert_eq!(
+            CATALOGS[0].registered_path(),
+            Path::new(DIRNAME)
+                .join("fr_FR")
+                .join("LC_MESSAGES")
+                .join("hello.mo")
+        );
+    }
+
+    #[test]
+    fn catalogs_are_found_by_language() {
+        assert_eq!(
+            find(CATALOGS, "fr_FR", "LC_MESSAGES", "hello"),
+            Some(CATALOGS[0].registered_path())
+        );
+        assert_eq!(find(CATALOGS, "fr", "LC_MESSAGES", "hello"), None);
+        assert_eq!(find(CATALOGS, "de", "LC_MESSAGES", "hello"), None);
+        assert_eq!(
+            find(CATALOGS, "de", "LC_TIME", "hello"),
+            Some(CATALOGS[1].registered_path())
+        );
+        assert_eq!(find(CATALOGS, "fr_FR", "LC_MESSAGES", "hell"), None);
+    }
+
+    #[test]
+    fn translations_are_looked_up_in_registered_catalogs() {
+        let data = ffi::mo_file(&[("Hello, world!", "Bonjour, le monde !")], false, true);
+        register(&[EmbeddedCatalog {
+            path: "fr/LC_MESSAGES/embedded_test.mo",
+            data: Box::leak(data.into_boxed_slice()),
+        }]);
+
+        // Other tests change the locale too, but never to "C", so the catalog is always used.
+        ::setlocale(::LocaleCategory::LcMessages, "fr_FR");
+        assert_eq!(
+            ::dgettext("embedded_test", "Hello, world!"),
+            "Bonjour, le monde !"
+        );
+        assert_eq!(
+            ::getters::domain_directory("embedded_test").unwrap(),
+            PathBuf::from(DIRNAME)
+        );
+    }
+
+    #[test]
+    fn untranslated_messages_of_registered_domains_are_not_errors() {
+        let data = ffi::mo_file(&[("Hello, world!", "Hallo, Welt!")], false, true);
+        register(&[EmbeddedCatalog {
+            path: "de/LC_MESSAGES/embedded_fallible_test.mo",
+            data: Box::leak(data.into_boxed_slice()),
+        }]);
+
+        assert_eq!(
+            ::fallible::dgettext("embedded_fallible_test", "Not translated").unwrap(),
+            "Not translated"
+        );
+    }
+}
@@ -0,0 +1,609 @@
+//! Translation functions that return errors instead of panicking.
+//!
+//! The functions at the crate root panic if an argument contains an internal 0 byte, or if
+//! gettext returns something that isn't UTF-8. That's convenient for string literals, but not
+//! for messages that depend on user input. The functions in this module mirror those at the
+//! crate root, but report such problems as a [`TranslationError`].
+//!
+//! They also check one thing that the panicking functions don't: if a message wasn't translated,
+//! they make sure that the text domain has an existing directory bound to it. That catches the
+//! common mistake of forgetting to call [`bindtextdomain`][::bindtextdomain] (or
+//! [`TextDomain::init`][::TextDomain::init]). Note that libintl falls back to a compiled-in
+//! default directory (usually _/usr/share/locale_) for domains that were never bound, so if that
+//! directory exists, the domain is considered bound.
+//!
+//! ```rust,no_run
+//! use gettextrs::fallible::{self, TranslationError};
+//!
+//! # fn main() -> Result<(), TranslationError> {
+//! let msgid = String::from("Hello, world!");
+//! println!("Translated: {}", fallible::gettext(msgid)?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_char;
+use std::string::FromUtf8Error;
+
+use super::{build_context_id, getters, LocaleCategory, PluralCount, CONTEXT_SEPARATOR};
+#[cfg(feature = "pure-rust")]
+use embedded;
+use ffi;
+
+/// An argument of a translation function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Argument {
+    /// The `domainname` argument.
+    Domainname,
+    /// The `msgctxt` argument.
+    Msgctxt,
+    /// The `msgid` argument.
+    Msgid,
+    /// The `msgid_plural` argument.
+    MsgidPlural,
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Argument::*;
+
+        let name = match self {
+            Domainname => "domainname",
+            Msgctxt => "msgctxt",
+            Msgid => "msgid",
+            MsgidPlural => "msgid_plural",
+        };
+        write!(f, "`{}`", name)
+    }
+}
+
+/// Errors that might come up when translating a message.
+#[derive(Debug)]
+pub enum TranslationError {
+    /// The argument contains an internal 0 byte, so it can't be passed to the underlying C API.
+    InteriorNul(Argument),
+    /// The result is not in UTF-8 (see [this note](../index.html#utf-8-is-required)). The raw
+    /// bytes can be retrieved with [`FromUtf8Error::as_bytes`] or [`FromUtf8Error::into_bytes`].
+    InvalidUtf8(FromUtf8Error),
+    /// The message wasn't translated, and the text domain with the given name doesn't have an
+    /// existing directory bound to it.
+    DomainNotBound(String),
+}
+
+impl fmt::Display for TranslationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::TranslationError::*;
+
+        match self {
+            InteriorNul(argument) => write!(f, "{} contains an internal 0 byte", argument),
+            InvalidUtf8(inner) => write!(f, "The translation is not valid UTF-8: {}", inner),
+            DomainNotBound(domainname) => write!(
+                f,
+                r#"Text domain "{}" is not bound to an existing directory."#,
+                domainname
+            ),
+        }
+    }
+}
+
+impl error::Error for TranslationError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use self::TranslationError::*;
+
+        match self {
+            InteriorNul(_) => None,
+            InvalidUtf8(inner) => Some(inner),
+            DomainNotBound(_) => None,
+        }
+    }
+}
+
+fn to_cstring(value: String, argument: Argument) -> Result<CString, TranslationError> {
+    CString::new(value).map_err(|_| TranslationError::InteriorNul(argument))
+}
+
+/// Checks that `domainname` (or the current text domain, if `None`) is bound to a directory that
+/// exists, or to the catalogs embedded in the program.
+fn check_domain_bound(domainname: Option<&CStr>) -> Result<(), TranslationError> {
+    let domainname = match domainname {
+        Some(domainname) => domainname.to_bytes().to_owned(),
+        None => getters::current_textdomain().unwrap_or_default(),
+    };
+
+    match getters::domain_directory(domainname.clone()) {
+        Ok(ref directory) if directory.is_dir() => Ok(()),
+        #[cfg(feature = "pure-rust")]
+        Ok(ref directory) if embedded::is_dirname(directory) => Ok(()),
+        _ => Err(TranslationError::DomainNotBound(
+            String::from_utf8_lossy(&domainname).into_owned(),
+        )),
+    }
+}
+
+/// Converts the result of a gettext call into a `String`.
+///
+/// gettext returns one of the original pointers if it couldn't find a translation; in that case,
+/// this function checks that the domain is bound.
+///
+/// # Safety
+///
+/// `result` must be a valid pointer to a C string.
+unsafe fn translation(
+    domainname: Option<&CStr>,
+    msgids: &[&CString],
+    result: *const c_char,
+) -> Result<String, TranslationError> {
+    if msgids.iter().any(|msgid| msgid.as_ptr() == result) {
+        check_domain_bound(domainname)?;
+    }
+
+    let bytes = CStr::from_ptr(result).to_bytes().to_owned();
+    String::from_utf8(bytes).map_err(TranslationError::InvalidUtf8)
+}
+
+/// Translate msgid to localized message from the default domain.
+///
+/// This is a non-panicking version of [`gettext`][::gettext()].
+pub fn gettext<T: Into<String>>(msgid: T) -> Result<String, TranslationError> {
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    unsafe { translation(None, &[&msgid], ffi::gettext(msgid.as_ptr())) }
+}
+
+/// Translate msgid to localized message from the specified domain.
+///
+/// This is a non-panicking version of [`dgettext`][::dgettext].
+pub fn dgettext<T, U>(domainname: T, msgid: U) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+{
+    let domainname = to_cstring(domainname.into(), Argument::Domainname)?;
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    unsafe {
+        translation(
+            Some(&domainname),
+            &[&msgid],
+            ffi::dgettext(domainname.as_ptr(), msgid.as_ptr()),
+        )
+    }
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category.
+///
+/// This is a non-panicking version of [`dcgettext`][::dcgettext].
+pub fn dcgettext<T, U>(
+    domainname: T,
+    msgid: U,
+    category: LocaleCategory,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+{
+    let domainname = to_cstring(domainname.into(), Argument::Domainname)?;
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    unsafe {
+        translation(
+            Some(&domainname),
+            &[&msgid],
+            ffi::dcgettext(domainname.as_ptr(), msgid.as_ptr(), category as i32),
+        )
+    }
+}
+
+/// Translate msgid to localized message from the default domain (with plural support).
+///
+/// This is a non-panicking version of [`ngettext`][::ngettext()].
+pub fn ngettext<T, S, N>(msgid: T, msgid_plural: S, n: N) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    S: Into<String>,
+    N: PluralCount,
+{
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    let msgid_plural = to_cstring(msgid_plural.into(), Argument::MsgidPlural)?;
+    unsafe {
+        translation(
+            None,
+            &[&msgid, &msgid_plural],
+            ffi::ngettext(msgid.as_ptr(), msgid_plural.as_ptr(), n.to_c_ulong()),
+        )
+    }
+}
+
+/// Translate msgid to localized message from the specified domain (with plural support).
+///
+/// This is a non-panicking version of [`dngettext`][::dngettext].
+pub fn dngettext<T, U, V, N>(
+    domainname: T,
+    msgid: U,
+    msgid_plural: V,
+    n: N,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    N: PluralCount,
+{
+    let domainname = to_cstring(domainname.into(), Argument::Domainname)?;
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    let msgid_plural = to_cstring(msgid_plural.into(), Argument::MsgidPlural)?;
+    unsafe {
+        translation(
+            Some(&domainname),
+            &[&msgid, &msgid_plural],
+            ffi::dngettext(
+                domainname.as_ptr(),
+                msgid.as_ptr(),
+                msgid_plural.as_ptr(),
+                n.to_c_ulong(),
+            ),
+        )
+    }
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category
+/// (with plural support).
+///
+/// This is a non-panicking version of [`dcngettext`][::dcngettext].
+pub fn dcngettext<T, U, V, N>(
+    domainname: T,
+    msgid: U,
+    msgid_plural: V,
+    n: N,
+    category: LocaleCategory,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    N: PluralCount,
+{
+    let domainname = to_cstring(domainname.into(), Argument::Domainname)?;
+    let msgid = to_cstring(msgid.into(), Argument::Msgid)?;
+    let msgid_plural = to_cstring(msgid_plural.into(), Argument::MsgidPlural)?;
+    unsafe {
+        translation(
+            Some(&domainname),
+            &[&msgid, &msgid_plural],
+            ffi::dcngettext(
+                domainname.as_ptr(),
+                msgid.as_ptr(),
+                msgid_plural.as_ptr(),
+                n.to_c_ulong(),
+                category as i32,
+            ),
+        )
+    }
+}
+
+fn check_ctxt(msgctxt: &str) -> Result<(), TranslationError> {
+    if msgctxt.contains('\0') {
+        Err(TranslationError::InteriorNul(Argument::Msgctxt))
+    } else {
+        Ok(())
+    }
+}
+
+/// Translate msgid to localized message from the default domain (with context support).
+///
+/// This is a non-panicking version of [`pgettext`][::pgettext()].
+pub fn pgettext<T, U>(msgctxt: T, msgid: U) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+{
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let msgid = msgid.into();
+    let text = build_context_id(&msgctxt, &msgid);
+
+    let translation = gettext(text)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return gettext(msgid);
+    }
+
+    Ok(translation)
+}
+
+/// Translate msgid to localized message from the default domain (with plural support and context
+/// support).
+///
+/// This is a non-panicking version of [`npgettext`][::npgettext].
+pub fn npgettext<T, U, V, N>(
+    msgctxt: T,
+    msgid: U,
+    msgid_plural: V,
+    n: N,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    N: PluralCount,
+{
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let singular_msgid = msgid.into();
+    let plural_msgid = msgid_plural.into();
+    let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+    let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+    let translation = ngettext(singular_ctxt, plural_ctxt, n)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return ngettext(singular_msgid, plural_msgid, n);
+    }
+
+    Ok(translation)
+}
+
+/// Translate msgid to localized message from the specified domain (with context support).
+///
+/// This is a non-panicking version of [`dpgettext`][::dpgettext].
+pub fn dpgettext<T, U, V>(domainname: T, msgctxt: U, msgid: V) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let msgid = msgid.into();
+    let text = build_context_id(&msgctxt, &msgid);
+
+    let translation = dgettext(domainname.clone(), text)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dgettext(domainname, msgid);
+    }
+
+    Ok(translation)
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category
+/// (with context support).
+///
+/// This is a non-panicking version of [`dcpgettext`][::dcpgettext].
+pub fn dcpgettext<T, U, V>(
+    domainname: T,
+    msgctxt: U,
+    msgid: V,
+    category: LocaleCategory,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let msgid = msgid.into();
+    let text = build_context_id(&msgctxt, &msgid);
+
+    let translation = dcgettext(domainname.clone(), text, category)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dcgettext(domainname, msgid, category);
+    }
+
+    Ok(translation)
+}
+
+/// Translate msgid to localized message from the specified domain (with plural support and
+/// context support).
+///
+/// This is a non-panicking version of [`dnpgettext`][::dnpgettext].
+pub fn dnpgettext<T, U, V, W, N>(
+    domainname: T,
+    msgctxt: U,
+    msgid: V,
+    msgid_plural: W,
+    n: N,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    W: Into<String>,
+    N: PluralCount,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let singular_msgid = msgid.into();
+    let plural_msgid = msgid_plural.into();
+    let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+    let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+    let translation = dngettext(domainname.clone(), singular_ctxt, plural_ctxt, n)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dngettext(domainname, singular_msgid, plural_msgid, n);
+    }
+
+    Ok(translation)
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category
+/// (with plural support and context support).
+///
+/// This is a non-panicking version of [`dcnpgettext`][::dcnpgettext].
+pub fn dcnpgettext<T, U, V, W, N>(
+    domainname: T,
+    msgctxt: U,
+    msgid: V,
+    msgid_plural: W,
+    n: N,
+    category: LocaleCategory,
+) -> Result<String, TranslationError>
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    W: Into<String>,
+    N: PluralCount,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    check_ctxt(&msgctxt)?;
+
+    let singular_msgid = msgid.into();
+    let plural_msgid = msgid_plural.into();
+    let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+    let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+    let translation = dcngettext(domainname.clone(), singular_ctxt, plural_ctxt, n, category)?;
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dcngettext(domainname, singular_msgid, plural_msgid, n, category);
+    }
+
+    Ok(translation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bindtextdomain;
+
+    fn assert_nul_in(result: Result<String, TranslationError>, expected: Argument) {
+        match result {
+            Err(TranslationError::InteriorNul(argument)) => assert_eq!(argument, expected),
+            other => panic!("expected InteriorNul({:?}), got {:?}", expected, other),
+        }
+    }
+
+    #[test]
+    fn interior_nul_is_reported_per_argument() {
+        assert_nul_in(gettext("input string\0"), Argument::Msgid);
+
+        assert_nul_in(dgettext("hello\0world!", "hi"), Argument::Domainname);
+        assert_nul_in(dgettext("hello world", "che\0ck"), Argument::Msgid);
+
+        assert_nul_in(
+            dcgettext("diff\0erent", "hello", LocaleCategory::LcAll),
+            Argument::Domainname,
+        );
+        assert_nul_in(
+            dcgettext("world", "\0ne", LocaleCategory::LcMessages),
+            Argument::Msgid,
+        );
+
+        assert_nul_in(ngettext("singular\0form", "plural", 10), Argument::Msgid);
+        assert_nul_in(
+            ngettext("singular", "plural\0form", 0),
+            Argument::MsgidPlural,
+        );
+
+        assert_nul_in(
+            dngettext("do\0main", "one", "many", 0),
+            Argument::Domainname,
+        );
+        assert_nul_in(dngettext("domain", "o\0ne", "many", 0), Argument::Msgid);
+        assert_nul_in(
+            dngettext("domain", "one", "ma\0ny", 0),
+            Argument::MsgidPlural,
+        );
+
+        assert_nul_in(
+            dcngettext("d\0", "one", "few", 1, LocaleCategory::LcTime),
+            Argument::Domainname,
+        );
+        assert_nul_in(
+            dcngettext("d", "\0ne", "few", 1, LocaleCategory::LcTime),
+            Argument::Msgid,
+        );
+        assert_nul_in(
+            dcngettext("d", "one", "a\0few", 1, LocaleCategory::LcTime),
+            Argument::MsgidPlural,
+        );
+
+        assert_nul_in(pgettext("context\0", "string"), Argument::Msgctxt);
+        assert_nul_in(pgettext("ctx", "a message\0"), Argument::Msgid);
+
+        assert_nul_in(dpgettext("d\0", "ctx", "one"), Argument::Domainname);
+        assert_nul_in(dpgettext("d", "c\0tx", "one"), Argument::Msgctxt);
+        assert_nul_in(dpgettext("d", "ctx", "o\0ne"), Argument::Msgid);
+
+        assert_nul_in(
+            dcpgettext("d\0", "ctx", "one", LocaleCategory::LcAll),
+            Argument::Domainname,
+        );
+        assert_nul_in(
+            dcpgettext("d", "c\0tx", "one", LocaleCategory::LcAll),
+            Argument::Msgctxt,
+        );
+        assert_nul_in(
+            dcpgettext("d", "ctx", "o\0ne", LocaleCategory::LcAll),
+            Argument::Msgid,
+        );
+
+        assert_nul_in(
+            dnpgettext("d\0", "ctx", "one", "many", 1),
+            Argument::Domainname,
+        );
+        assert_nul_in(
+            dnpgettext("d", "c\0tx", "one", "many", 1),
+            Argument::Msgctxt,
+        );
+        assert_nul_in(dnpgettext("d", "ctx", "o\0ne", "many", 1), Argument::Msgid);
+        assert_nul_in(
+            dnpgettext("d", "ctx", "one", "ma\0ny", 1),
+            Argument::MsgidPlural,
+        );
+
+        assert_nul_in(
+            dcnpgettext("d\0", "ctx", "one", "many", 1, LocaleCategory::LcAll),
+            Argument::Domainname,
+        );
+        assert_nul_in(
+            dcnpgettext("d", "c\0tx", "one", "many", 1, LocaleCategory::LcAll),
+            Argument::Msgctxt,
+        );
+        assert_nul_in(
+            dcnpgettext("d", "ctx", "o\0ne", "many", 1, LocaleCategory::LcAll),
+            Argument::Msgid,
+        );
+        assert_nul_in(
+            dcnpgettext("d", "ctx", "one", "ma\0ny", 1, LocaleCategory::LcAll),
+            Argument::MsgidPlural,
+        );
+
+        assert_nul_in(npgettext("c\0tx", "one", "many", 0), Argument::Msgctxt);
+        assert_nul_in(npgettext("ctx", "o\0ne", "many", 0), Argument::Msgid);
+        assert_nul_in(npgettext("ctx", "one", "ma\0ny", 0), Argument::MsgidPlural);
+    }
+
+    #[test]
+    fn untranslated_messages_are_returned_from_bound_domains() {
+        bindtextdomain("fallible_bound", env!("CARGO_MANIFEST_DIR")).unwrap();
+
+        assert_eq!(
+            dgettext("fallible_bound", "Hello, world!").unwrap(),
+            "Hello, world!"
+        );
+        assert_eq!(
+            dngettext("fallible_bound", "Hello, world!", "Hello, worlds!", 2).unwrap(),
+            "Hello, worlds!"
+        );
+    }
+
+    #[test]
+    fn domain_not_bound() {
+        bindtextdomain("fallible_unbound", "/this/directory/does/not/exist").unwrap();
+
+        match dgettext("fallible_unbound", "Hello, world!") {
+            Err(TranslationError::DomainNotBound(domainname)) => {
+                assert_eq!(domainname, "fallible_unbound")
+            }
+            other => panic!("expected DomainNotBound, got {:?}", other),
+        }
+    }
+}
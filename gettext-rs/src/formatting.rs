@@ -0,0 +1,378 @@
+//! Substitution of arguments into translated strings.
+//!
+//! `format!` only accepts string literals, so it can't be used with the results of
+//! [`gettext`][::gettext()] and other functions. This module implements a small subset of its
+//! syntax that works at runtime:
+//!
+//! - `{}` is replaced with the next positional argument;
+//! - `{0}`, `{1}` etc. are replaced with the positional argument with that index;
+//! - `{name}` is replaced with the argument named `name`;
+//! - `{{` and `}}` stand for literal braces.
+//!
+//! Format specs like `{:>8}` are not supported. The arguments are formatted with their
+//! `Display` implementations.
+//!
+//! Most of the time, you'll use this module through macros like [`gettext_fmt!`], which translate
+//! a message and then substitute the arguments:
+//!
+//! ```rust,no_run
+//! #[macro_use]
+//! extern crate gettextrs;
+//!
+//! fn main() {
+//!     let user = "Alice";
+//!     let count = 3;
+//!     println!("{}", gettext_fmt!("Hello, {name}!", name = user));
+//!     println!(
+//!         "{}",
+//!         ngettext_fmt!("{} has one message", "{} has {n} messages", count, user, n = count)
+//!     );
+//! }
+//! ```
+//!
+//! Translators make mistakes, so the macros check the translation before using it. If it contains
+//! unmatched braces or placeholders that don't match any argument (e.g. because a name was
+//! misspelt), or if it lacks a placeholder that the original message has, the macros fall back to
+//! the original message. Plural forms may omit placeholders, though, as languages often don't
+//! mention the number in singular.
+//!
+//! [`gettext_fmt!`]: ../macro.gettext_fmt.html
+
+use std::error;
+use std::fmt::{self, Display, Write};
+
+use super::PluralCount;
+
+/// Errors that might come up while substituting arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// There's an unmatched `{` or `}` at the given byte offset.
+    UnmatchedBrace(usize),
+    /// The placeholder doesn't match any argument. Contains the text between the braces.
+    UnknownPlaceholder(String),
+    /// The placeholder is used in the original message, but not in the translation. Contains the
+    /// text between the braces, or the index of the argument for `{}`.
+    MissingPlaceholder(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::FormatError::*;
+
+        match self {
+            UnmatchedBrace(offset) => write!(f, "Unmatched brace at offset {}.", offset),
+            UnknownPlaceholder(placeholder) => {
+                write!(
+                    f,
+                    "Placeholder {{{}}} doesn't match any argument.",
+                    placeholder
+                )
+            }
+            MissingPlaceholder(placeholder) => {
+                write!(f, "Placeholder {{{}}} is missing.", placeholder)
+            }
+        }
+    }
+}
+
+impl error::Error for FormatError {}
+
+/// An argument to substitute.
+#[derive(Clone, Copy)]
+pub struct Argument<'a> {
+    name: Option<&'a str>,
+    value: &'a dyn Display,
+}
+
+impl<'a> Argument<'a> {
+    /// Creates a positional argument, which is substituted for `{}` and `{0}`-style placeholders.
+    pub fn positional(value: &'a dyn Display) -> Argument<'a> {
+        Argument { name: None, value }
+    }
+
+    /// Creates a named argument, which is substituted for `{name}` placeholders.
+    pub fn named(name: &'a str, value: &'a dyn Display) -> Argument<'a> {
+        Argument {
+            name: Some(name),
+            value,
+        }
+    }
+}
+
+/// A placeholder, resolved to the argument it refers to.
+#[derive(PartialEq)]
+enum Placeholder<'t> {
+    Positional(usize),
+    Named(&'t str),
+}
+
+enum Segment<'t> {
+    Literal(&'t str),
+    /// A placeholder, and the text between its braces.
+    Placeholder(Placeholder<'t>, &'t str),
+}
+
+/// Splits `template` into literal text and placeholders.
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, FormatError> {
+    let mut segments = vec![];
+    let mut next_positional = 0;
+    let mut rest = template;
+    while let Some(position) = rest.find(['{', '}']) {
+        let offset = template.len() - rest.len() + position;
+        segments.push(Segment::Literal(&rest[..position]));
+
+        let brace = &rest[position..=position];
+        if rest[position + 1..].starts_with(brace) {
+            segments.push(Segment::Literal(brace));
+            rest = &rest[position + 2..];
+            continue;
+        }
+        if brace == "}" {
+            return Err(FormatError::UnmatchedBrace(offset));
+        }
+
+        let end = match rest[position + 1..].find(['{', '}']) {
+            Some(end) if rest[position + 1 + end..].starts_with('}') => position + 1 + end,
+            _ => return Err(FormatError::UnmatchedBrace(offset)),
+        };
+        let text = rest[position + 1..end].trim();
+        let placeholder = if text.is_empty() {
+            next_positional += 1;
+            Placeholder::Positional(next_positional - 1)
+        } else if text.bytes().all(|byte| byte.is_ascii_digit()) {
+            match text.parse() {
+                Ok(index) => Placeholder::Positional(index),
+                Err(_) => return Err(FormatError::UnknownPlaceholder(text.to_owned())),
+            }
+        } else {
+            Placeholder::Named(text)
+        };
+        segments.push(Segment::Placeholder(placeholder, text));
+
+        rest = &rest[end + 1..];
+    }
+    segments.push(Segment::Literal(rest));
+
+    Ok(segments)
+}
+
+fn find<'a>(arguments: &[Argument<'a>], placeholder: &Placeholder) -> Option<&'a dyn Display> {
+    match *placeholder {
+        Placeholder::Positional(index) => arguments
+            .iter()
+            .filter(|argument| argument.name.is_none())
+            .nth(index),
+        Placeholder::Named(name) => arguments
+            .iter()
+            .find(|argument| argument.name == Some(name)),
+    }
+    .map(|argument| argument.value)
+}
+
+/// Substitutes `arguments` into `template`.
+///
+/// ```rust
+/// use gettextrs::formatting::{format, Argument, FormatError};
+///
+/// let count = 3;
+/// let arguments = [Argument::positional(&"Alice"), Argument::named("n", &count)];
+/// assert_eq!(
+///     format("{} has {n} messages, {{n}} is literal", &arguments),
+///     Ok("Alice has 3 messages, {n} is literal".to_owned())
+/// );
+/// assert_eq!(
+///     format("{} has {m} messages", &arguments),
+///     Err(FormatError::UnknownPlaceholder("m".to_owned()))
+/// );
+/// ```
+pub fn format(template: &str, arguments: &[Argument]) -> Result<String, FormatError> {
+    let mut result = String::with_capacity(template.len());
+    for segment in parse(template)? {
+        match segment {
+            Segment::Literal(text) => result.push_str(text),
+            Segment::Placeholder(placeholder, text) => match find(arguments, &placeholder) {
+                Some(value) => write!(result, "{}", value)
+                    .expect("a Display implementation returned an error unexpectedly"),
+                None => return Err(FormatError::UnknownPlaceholder(text.to_owned())),
+            },
+        }
+    }
+
+    Ok(result)
+}
+
+/// Checks that `translation` uses all the placeholders that `original` uses.
+fn check_placeholders(translation: &str, original: &str) -> Result<(), FormatError> {
+    let used = parse(translation)?;
+    for segment in parse(original)? {
+        if let Segment::Placeholder(placeholder, text) = segment {
+            let is_used = used.iter().any(|segment| match segment {
+                Segment::Placeholder(other, _) => *other == placeholder,
+                Segment::Literal(_) => false,
+            });
+            if !is_used {
+                let text = match placeholder {
+                    Placeholder::Positional(index) if text.is_empty() => index.to_string(),
+                    _ => text.to_owned(),
+                };
+                return Err(FormatError::MissingPlaceholder(text));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Substitutes `arguments` into `translation`, falling back to `original` if that fails (see
+/// [module documentation](index.html)). Used by the macros.
+///
+/// # Panics
+///
+/// Panics if `arguments` can't be substituted into `original`, as that's a bug in the program
+/// rather than in the translation.
+#[doc(hidden)]
+pub fn format_translation(
+    translation: &str,
+    original: &str,
+    arguments: &[Argument],
+    require_all_placeholders: bool,
+) -> String {
+    let checked = if require_all_placeholders {
+        check_placeholders(translation, original)
+    } else {
+        Ok(())
+    };
+
+    match checked.and_then(|_| format(translation, arguments)) {
+        Ok(result) => result,
+        Err(_) => format(original, arguments).unwrap_or_else(|error| {
+            panic!("can't substitute arguments into {:?}: {}", original, error)
+        }),
+    }
+}
+
+/// Like [`format_translation`], but falls back to `msgid` or `msgid_plural` depending on `n`, like
+/// gettext does when there's no translation, and doesn't require the translation to use all
+/// placeholders.
+#[doc(hidden)]
+pub fn format_plural_translation<N>(
+    translation: &str,
+    msgid: &str,
+    msgid_plural: &str,
+    n: N,
+    arguments: &[Argument],
+) -> String
+where
+    N: PluralCount,
+{
+    let original = if n.to_c_ulong() == 1 {
+        msgid
+    } else {
+        msgid_plural
+    };
+    format_translation(translation, original, arguments, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let arguments = [
+            Argument::positional(&1),
+            Argument::named("name", &"Alice"),
+            Argument::positional(&"two"),
+        ];
+
+        assert_eq!(
+            format("no placeholders", &arguments).unwrap(),
+            "no placeholders"
+        );
+        assert_eq!(format("{} {}", &arguments).unwrap(), "1 two");
+        assert_eq!(format("{1} {0} {1}", &arguments).unwrap(), "two 1 two");
+        assert_eq!(
+            format("{name}, { name }!", &arguments).unwrap(),
+            "Alice, Alice!"
+        );
+        assert_eq!(
+            format("{{}} {{{name}}} }}{{", &arguments).unwrap(),
+            "{} {Alice} }{"
+        );
+        assert_eq!(
+            format("Привет, {name}", &arguments).unwrap(),
+            "Привет, Alice"
+        );
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let arguments = [Argument::positional(&1), Argument::named("name", &"Alice")];
+
+        assert_eq!(
+            format("{} {}", &arguments),
+            Err(FormatError::UnknownPlaceholder("".to_owned()))
+        );
+        assert_eq!(
+            format("{5}", &arguments),
+            Err(FormatError::UnknownPlaceholder("5".to_owned()))
+        );
+        assert_eq!(
+            format("Hello, {nmae}", &arguments),
+            Err(FormatError::UnknownPlaceholder("nmae".to_owned()))
+        );
+        assert_eq!(
+            format("Hello, {name", &arguments),
+            Err(FormatError::UnmatchedBrace(7))
+        );
+        assert_eq!(
+            format("Hello, {na{me}", &arguments),
+            Err(FormatError::UnmatchedBrace(7))
+        );
+        assert_eq!(
+            format("Hello, name}", &arguments),
+            Err(FormatError::UnmatchedBrace(11))
+        );
+    }
+
+    #[test]
+    fn broken_translations_fall_back_to_original() {
+        let arguments = [Argument::positional(&3), Argument::named("name", &"Alice")];
+        let original = "{name} has {} messages";
+
+        assert_eq!(
+            format_translation("{name} a {} messages", original, &arguments, true),
+            "Alice a 3 messages"
+        );
+        assert_eq!(
+            format_translation("{0} messages pour {name}", original, &arguments, true),
+            "3 messages pour Alice"
+        );
+        assert_eq!(
+            format_translation("{nom} a {} messages", original, &arguments, true),
+            "Alice has 3 messages"
+        );
+        assert_eq!(
+            format_translation("{name} a des messages", original, &arguments, true),
+            "Alice has 3 messages"
+        );
+        assert_eq!(
+            format_translation("{name} a un message", original, &arguments, false),
+            "Alice a un message"
+        );
+        assert_eq!(
+            format_translation("{name} a {} messages}", original, &arguments, false),
+            "Alice has 3 messages"
+        );
+        assert_eq!(
+            check_placeholders("{name}", original),
+            Err(FormatError::MissingPlaceholder("0".to_owned()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't substitute arguments")]
+    fn broken_original_panics() {
+        format_translation("{}", "{} {}", &[Argument::positional(&1)], true);
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! There are just a few settings in gettext. The only required one is the message domain, set
 //! using [`textdomain`][::textdomain]; the other two are the path where translations are searched
-//! for, and the encoding to which the messages should be converted.
+//! for, and the encoding to which the messages should be converted. The locale, set using
+//! [`setlocale`][::setlocale], can be queried here too.
 //!
 //! The underlying C API uses the same functions both as setters and as getters: to get the current
 //! value, you just pass `NULL` as an argument. This is ergonomic in C, but not in Rust: wrapping
@@ -10,13 +11,14 @@
 //! functions. They're in a module of their own to prevent them from clashing with any functions
 //! that the underlying C API might gain in the future.
 
-extern crate gettext_sys as ffi;
-
 use std::ffi::{CStr, CString};
 use std::io;
 use std::path::PathBuf;
 use std::ptr;
 
+use super::{last_error, LocaleCategory};
+use ffi;
+
 /// Get currently set message domain.
 ///
 /// If you want to *set* the domain, rather than getting its current value, use
@@ -29,7 +31,7 @@ pub fn current_textdomain() -> Result<Vec<u8>, io::Error> {
     unsafe {
         let result = ffi::textdomain(ptr::null());
         if result.is_null() {
-            Err(io::Error::last_os_error())
+            Err(last_error())
         } else {
             Ok(CStr::from_ptr(result).to_bytes().to_owned())
         }
@@ -60,7 +62,7 @@ pub fn domain_directory<T: Into<Vec<u8>>>(domainname: T) -> Result<PathBuf, io::
         unsafe {
             let mut ptr = ffi::wbindtextdomain(domainname.as_ptr(), ptr::null());
             if ptr.is_null() {
-                Err(io::Error::last_os_error())
+                Err(last_error())
             } else {
                 let mut result = vec![];
                 while *ptr != 0_u16 {
@@ -72,7 +74,7 @@ pub fn domain_directory<T: Into<Vec<u8>>>(domainname: T) -> Result<PathBuf, io::
         }
     }
 
-    #[cfg(not(windows))]
+    #[cfg(unix)]
     {
         use std::ffi::OsString;
         use std::os::unix::ffi::OsStringExt;
@@ -80,7 +82,7 @@ pub fn domain_directory<T: Into<Vec<u8>>>(domainname: T) -> Result<PathBuf, io::
         unsafe {
             let result = ffi::bindtextdomain(domainname.as_ptr(), ptr::null());
             if result.is_null() {
-                Err(io::Error::last_os_error())
+                Err(last_error())
             } else {
                 let result = CStr::from_ptr(result);
                 Ok(PathBuf::from(OsString::from_vec(
@@ -89,6 +91,19 @@ pub fn domain_directory<T: Into<Vec<u8>>>(domainname: T) -> Result<PathBuf, io::
             }
         }
     }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        unsafe {
+            let result = ffi::bindtextdomain(domainname.as_ptr(), ptr::null());
+            if result.is_null() {
+                Err(last_error())
+            } else {
+                let result = CStr::from_ptr(result);
+                Ok(PathBuf::from(result.to_string_lossy().into_owned()))
+            }
+        }
+    }
 }
 
 /// Get encoding of translated messages for given domain.
@@ -114,7 +129,7 @@ pub fn textdomain_codeset<T: Into<Vec<u8>>>(domainname: T) -> Result<Option<Stri
     unsafe {
         let result = ffi::bind_textdomain_codeset(domainname.as_ptr(), ptr::null());
         if result.is_null() {
-            let error = io::Error::last_os_error();
+            let error = last_error();
             if let Some(0) = error.raw_os_error() {
                 return Ok(None);
             } else {
@@ -129,3 +144,28 @@ pub fn textdomain_codeset<T: Into<Vec<u8>>>(domainname: T) -> Result<Option<Stri
         }
     }
 }
+
+/// Get the name of the locale currently set for the given category.
+///
+/// Returns `None` if the underlying C API doesn't know the category. For
+/// [`LocaleCategory::LcAll`], the result is either a single locale name, or a list of the
+/// locales of all categories if they're not the same, which can be passed back to
+/// [`setlocale`][::setlocale].
+///
+/// If you want to *set* the locale, rather than querying the current one, use
+/// [`setlocale`][::setlocale].
+///
+/// For more information, see [setlocale(3)][].
+///
+/// [`LocaleCategory::LcAll`]: ../enum.LocaleCategory.html#variant.LcAll
+/// [setlocale(3)]: https://www.man7.org/linux/man-pages/man3/setlocale.3.html
+pub fn current_locale(category: LocaleCategory) -> Option<Vec<u8>> {
+    unsafe {
+        let result = ffi::setlocale(category as i32, ptr::null());
+        if result.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(result).to_bytes().to_owned())
+        }
+    }
+}
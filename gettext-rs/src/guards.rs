@@ -0,0 +1,162 @@
+//! Temporarily change gettext settings.
+//!
+//! Each guard in this module changes a setting when it's created, remembering the previous value
+//! with the help of the [`getters`][::getters] module, and puts that value back when it's dropped.
+//! This is handy in tests and in subcommands that need another domain or locale for a while:
+//!
+//! ```no_run
+//! use gettextrs::guards::{SetLocaleGuard, TextDomainGuard};
+//! use gettextrs::{gettext, LocaleCategory};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! {
+//!     let _domain = TextDomainGuard::new("other_program")?;
+//!     let _locale = SetLocaleGuard::new(LocaleCategory::LcMessages, "fr_FR.UTF-8");
+//!     println!("{}", gettext("Hello, world!"));
+//! }
+//! // The previous domain and locale are back.
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Thread safety
+//!
+//! The settings changed by these guards are global to the process, and the guards don't
+//! synchronize with anything: other threads see the new value as long as a guard is alive, and
+//! the previous value is restored even if another thread changed the setting in the meantime. If
+//! guards for the same setting overlap in several threads, the one dropped last wins, which
+//! might not be the original value. So either use these guards while only one thread uses
+//! gettext, or serialize them yourself.
+//!
+//! To translate into another language in a single thread, use
+//! [`LocaleGuard`][::LocaleGuard] instead, where it's available.
+//!
+//! Guards for the same setting must be dropped in the reverse order of their creation, which
+//! happens by itself when they're scoped. Errors while restoring the previous value are ignored,
+//! as `drop` can't report them.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{bindtextdomain, setlocale, textdomain, LocaleCategory};
+use getters::{current_locale, current_textdomain, domain_directory};
+
+/// Changes the default text domain, like [`textdomain`][::textdomain()], until dropped.
+#[derive(Debug)]
+pub struct TextDomainGuard {
+    previous: Vec<u8>,
+}
+
+impl TextDomainGuard {
+    /// Makes `domainname` the default text domain.
+    ///
+    /// Returns an error if the current domain can't be queried, or the new one can't be set; the
+    /// setting is left unchanged in both cases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `domainname` contains an internal 0 byte, as such values can't be passed to the
+    /// underlying C API.
+    pub fn new<T: Into<Vec<u8>>>(domainname: T) -> Result<TextDomainGuard, io::Error> {
+        let previous = current_textdomain()?;
+        textdomain(domainname)?;
+        Ok(TextDomainGuard { previous })
+    }
+
+    /// Returns the domain that will be restored.
+    pub fn previous(&self) -> &[u8] {
+        &self.previous
+    }
+}
+
+impl Drop for TextDomainGuard {
+    fn drop(&mut self) {
+        let _ = textdomain(self.previous.clone());
+    }
+}
+
+/// Changes the locale of a category, like [`setlocale`], until dropped.
+#[derive(Debug)]
+pub struct SetLocaleGuard {
+    category: LocaleCategory,
+    previous: Vec<u8>,
+}
+
+impl SetLocaleGuard {
+    /// Sets the locale for `category` to `locale`.
+    ///
+    /// Returns `None` if the current locale can't be queried, or if [`setlocale`]
+    /// fails, e.g. because the locale isn't installed; the locale is left unchanged in both
+    /// cases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `locale` contains an internal 0 byte, as such values can't be passed to the
+    /// underlying C API.
+    pub fn new<T: Into<Vec<u8>>>(category: LocaleCategory, locale: T) -> Option<SetLocaleGuard> {
+        let previous = current_locale(category)?;
+        setlocale(category, locale)?;
+        Some(SetLocaleGuard { category, previous })
+    }
+
+    /// Returns the locale that will be restored.
+    pub fn previous(&self) -> &[u8] {
+        &self.previous
+    }
+}
+
+impl Drop for SetLocaleGuard {
+    fn drop(&mut self) {
+        setlocale(self.category, self.previous.clone());
+    }
+}
+
+/// Changes the directory of a text domain, like [`bindtextdomain`][::bindtextdomain()], until
+/// dropped.
+///
+/// A domain that wasn't bound yet uses the system's default directory, so that's the directory
+/// it gets bound to afterwards, which makes no difference to the lookups.
+#[derive(Debug)]
+pub struct BindTextDomainGuard {
+    domainname: Vec<u8>,
+    previous: PathBuf,
+}
+
+impl BindTextDomainGuard {
+    /// Makes `domainname` look for its catalogs in `dirname`.
+    ///
+    /// Returns an error if the current directory can't be queried, or the new one can't be set;
+    /// the setting is left unchanged in both cases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    /// * `domainname` or `dirname` contain an internal 0 byte, as such values can't be passed to
+    ///   the underlying C API;
+    /// * on targets other than Unix and Windows, `dirname` is not in UTF-8.
+    pub fn new<T, U>(domainname: T, dirname: U) -> Result<BindTextDomainGuard, io::Error>
+    where
+        T: Into<Vec<u8>>,
+        U: Into<PathBuf>,
+    {
+        let domainname = domainname.into();
+        let previous = domain_directory(domainname.clone())?;
+        bindtextdomain(domainname.clone(), dirname)?;
+        Ok(BindTextDomainGuard {
+            domainname,
+            previous,
+        })
+    }
+
+    /// Returns the directory that will be restored.
+    pub fn previous(&self) -> &Path {
+        &self.previous
+    }
+}
+
+impl Drop for BindTextDomainGuard {
+    fn drop(&mut self) {
+        let _ = bindtextdomain(self.domainname.clone(), self.previous.clone());
+    }
+}
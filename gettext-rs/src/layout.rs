@@ -0,0 +1,257 @@
+//! Layouts of the directories that hold catalogs, used by [`TextDomain`][::TextDomain].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The layout used by GNU gettext, relative to a search path.
+pub const DEFAULT_LAYOUT: &str = "locale/{language}/{category}/{domain}.mo";
+
+const LANGUAGE: &str = "{language}";
+const CATEGORY: &str = "{category}";
+const DOMAIN: &str = "{domain}";
+
+/// A template for the paths of catalogs, like `i18n/{domain}-{language}.mo`, split into the
+/// leading directories without placeholders, and the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    template: String,
+    root: PathBuf,
+    rest: Vec<String>,
+}
+
+impl Layout {
+    pub fn new(template: &str) -> Layout {
+        let mut components: Vec<&str> = template
+            .split(&['/', '\\'][..])
+            .filter(|component| !component.is_empty())
+            .collect();
+        // The last component is the file itself, so it's never part of the root.
+        let file = components.pop().unwrap_or("");
+        let root_len = components
+            .iter()
+            .position(|component| component.contains('{'))
+            .unwrap_or(components.len());
+        let rest = components[root_len..]
+            .iter()
+            .chain(Some(&file))
+            .map(|&component| component.to_owned())
+            .collect();
+        Layout {
+            template: template.to_owned(),
+            root: components[..root_len].iter().collect(),
+            rest,
+        }
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Returns the directory under `path` in which the placeholders start.
+    pub fn root_in(&self, path: &Path) -> PathBuf {
+        path.join(&self.root)
+    }
+
+    /// Returns the path of the catalog in `root_dir`, the directory returned by `root_in`.
+    pub fn catalog_path(
+        &self,
+        root_dir: &Path,
+        language: &str,
+        category: &str,
+        domain: &str,
+    ) -> PathBuf {
+        let mut path = root_dir.to_owned();
+        for component in &self.rest {
+            path.push(expand(component, category, domain).replace(LANGUAGE, language));
+        }
+        path
+    }
+
+    /// Returns the directory to pass to `bindtextdomain` so that libintl finds the catalogs in
+    /// `root_dir` on its own, or `None` if the layout doesn't end like GNU gettext's.
+    pub fn bind_dir(&self, root_dir: &Path, domain: &str) -> Option<PathBuf> {
+        let split = self.rest.len().checked_sub(3)?;
+        let (dirs, tail) = self.rest.split_at(split);
+        if tail != [LANGUAGE, CATEGORY, "{domain}.mo"]
+            || dirs
+                .iter()
+                .any(|dir| dir.contains(LANGUAGE) || dir.contains(CATEGORY))
+        {
+            return None;
+        }
+
+        let mut path = root_dir.to_owned();
+        for dir in dirs {
+            path.push(dir.replace(DOMAIN, domain));
+        }
+        Some(path)
+    }
+
+    /// Returns the catalogs for `category` and `domain` in `root_dir`, with their languages, by
+    /// matching the names of the files and directories against the layout.
+    pub fn find_catalogs(
+        &self,
+        root_dir: &Path,
+        category: &str,
+        domain: &str,
+    ) -> Vec<(String, PathBuf)> {
+        let patterns = self
+            .rest
+            .iter()
+            .map(|component| expand(component, category, domain))
+            .collect::<Vec<_>>();
+        let mut found = vec![];
+        walk(root_dir, &patterns, None, &mut found);
+        found
+    }
+}
+
+fn expand(component: &str, category: &str, domain: &str) -> String {
+    component
+        .replace(CATEGORY, category)
+        .replace(DOMAIN, domain)
+}
+
+/// Matches the entries of `dir` against the first of `patterns`, and the entries of the matching
+/// directories against the rest, collecting the files that match the last pattern.
+fn walk(
+    dir: &Path,
+    patterns: &[String],
+    language: Option<&str>,
+    found: &mut Vec<(String, PathBuf)>,
+) {
+    let (pattern, rest) = match patterns.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    for name in names {
+        let matched = match match_component(pattern, &name) {
+            Some(matched) => matched,
+            None => continue,
+        };
+        let language = match (language, matched) {
+            (Some(language), Some(matched)) if language != matched => continue,
+            (language, matched) => language.or(matched),
+        };
+
+        let path = dir.join(&name);
+        if !rest.is_empty() {
+            walk(&path, rest, language, found);
+        } else if let Some(language) = language {
+            if path.is_file() {
+                found.push((language.to_owned(), path));
+            }
+        }
+    }
+}
+
+/// Matches `name` against `pattern`, in which only `{language}` is left. Returns the language if
+/// the pattern has one.
+fn match_component<'a>(pattern: &str, name: &'a str) -> Option<Option<&'a str>> {
+    let position = match pattern.find(LANGUAGE) {
+        Some(position) => position,
+        None => return if pattern == name { Some(None) } else { None },
+    };
+
+    let (prefix, suffix) = (&pattern[..position], &pattern[position + LANGUAGE.len()..]);
+    if name.len() > prefix.len() + suffix.len()
+        && name.starts_with(prefix)
+        && name.ends_with(suffix)
+    {
+        Some(Some(&name[prefix.len()..name.len() - suffix.len()]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_utils::{create_empty_files, TempDir};
+
+    #[test]
+    fn layouts_are_split_at_the_first_placeholder() {
+        let layout = Layout::new(DEFAULT_LAYOUT);
+        assert_eq!(
+            layout.root_in(Path::new("/usr/share")),
+            Path::new("/usr/share/locale")
+        );
+        assert_eq!(
+            layout.catalog_path(
+                Path::new("/usr/share/locale"),
+                "pt_BR",
+                "LC_MESSAGES",
+                "app"
+            ),
+            Path::new("/usr/share/locale/pt_BR/LC_MESSAGES/app.mo")
+        );
+        assert_eq!(
+            layout.bind_dir(Path::new("/usr/share/locale"), "app"),
+            Some(PathBuf::from("/usr/share/locale"))
+        );
+
+        let layout = Layout::new("share/{domain}/locale/{language}/{category}/{domain}.mo");
+        assert_eq!(layout.root_in(Path::new("/opt")), Path::new("/opt/share"));
+        assert_eq!(
+            layout.bind_dir(Path::new("/opt/share"), "app"),
+            Some(PathBuf::from("/opt/share/app/locale"))
+        );
+
+        let layout = Layout::new("i18n/{domain}-{language}.mo");
+        assert_eq!(layout.root_in(Path::new("/game")), Path::new("/game/i18n"));
+        assert_eq!(
+            layout.catalog_path(Path::new("/game/i18n"), "fr", "LC_MESSAGES", "mod"),
+            Path::new("/game/i18n/mod-fr.mo")
+        );
+        assert_eq!(layout.bind_dir(Path::new("/game/i18n"), "mod"), None);
+
+        let layout = Layout::new("catalog.mo");
+        assert_eq!(layout.root_in(Path::new("/app")), Path::new("/app"));
+    }
+
+    #[test]
+    fn catalogs_are_found_by_matching_the_layout() {
+        let temp_dir = TempDir::new("layout");
+        let dir = temp_dir.path();
+        create_empty_files(
+            &[
+                "i18n/mod-fr.mo",
+                "i18n/mod-pt_BR.mo",
+                "i18n/mod-.mo",
+                "i18n/other-de.mo",
+                "nested/de/mod/de.mo",
+                "nested/es/mod/fr.mo",
+            ]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect::<Vec<_>>(),
+        );
+
+        let layout = Layout::new("i18n/{domain}-{language}.mo");
+        assert_eq!(
+            layout.find_catalogs(&layout.root_in(dir), "LC_MESSAGES", "mod"),
+            vec![
+                ("fr".to_owned(), dir.join("i18n/mod-fr.mo")),
+                ("pt_BR".to_owned(), dir.join("i18n/mod-pt_BR.mo")),
+            ]
+        );
+
+        let layout = Layout::new("nested/{language}/{domain}/{language}.mo");
+        assert_eq!(
+            layout.find_catalogs(&layout.root_in(dir), "LC_MESSAGES", "mod"),
+            vec![("de".to_owned(), dir.join("nested/de/mod/de.mo"))]
+        );
+    }
+}
@@ -76,23 +76,77 @@
 //! "fr_FR.UTF-8")`, but that would also hard-code the language, defeating the purpose of gettext:
 //! if you know the language in advance, you could just write all your strings in that language and
 //! be done with that.
+//!
+//! ## Handling errors instead of panicking
+//!
+//! Translation functions panic if their arguments contain an internal 0 byte, or if the result
+//! isn't UTF-8. If your messages come from untrusted input, use the functions from the
+//! [`fallible`] module, which return a [`fallible::TranslationError`] instead.
 
 extern crate locale_config;
 
+#[cfg(not(feature = "pure-rust"))]
 extern crate gettext_sys as ffi;
 
+#[cfg(not(any(feature = "gettext-sys", feature = "pure-rust")))]
+compile_error!(
+    "either the `gettext-sys` feature (enabled by default) or `pure-rust` is required; with \
+     `default-features = false`, add `features = [\"gettext-sys\"]` to keep using libintl"
+);
+
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::io;
 use std::os::raw::c_ulong;
 use std::path::PathBuf;
 
+#[cfg(feature = "pure-rust")]
+mod backend;
+mod layout;
+mod locale_name;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "pure-rust")]
+use backend as ffi;
+
+#[doc(hidden)]
+#[macro_use]
+pub mod macros;
+mod domain;
+pub use domain::Domain;
 mod text_domain;
-pub use text_domain::{TextDomain, TextDomainError};
+pub use text_domain::{
+    FoundCatalog, InitOutcome, SearchReport, SearchedPath, TextDomain, TextDomainError,
+};
+#[cfg(any(
+    feature = "pure-rust",
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+mod thread_locale;
+#[cfg(any(
+    feature = "pure-rust",
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+pub use thread_locale::{with_locale, LocaleGuard};
+pub mod borrowed;
+#[cfg(feature = "pure-rust")]
+pub mod embedded;
+pub mod fallible;
+pub mod formatting;
 pub mod getters;
+pub mod guards;
+pub mod plural;
 
 /// Locale category enum ported from locale.h.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum LocaleCategory {
     /// Character classification and case conversion.
     LcCType = 0,
@@ -122,6 +176,61 @@ pub enum LocaleCategory {
     LcIdentification = 12,
 }
 
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integer types that can be used as the count in [`ngettext`][ngettext()] and other functions
+/// with plural support.
+///
+/// The underlying C API takes the count as `unsigned long`, which is only 32 bits wide on some
+/// platforms (e.g. Windows). Counts that don't fit are replaced with `n % 1000000 + 1000000`, as
+/// [recommended by the GNU gettext manual][gnu-plural]: this keeps the last six digits, which is
+/// all the plural rules of real-world languages look at, and makes sure the number doesn't turn
+/// into 0 or 1.
+///
+/// Signed types are accepted so that integer literals keep working. Negative counts are replaced
+/// with their absolute value.
+///
+/// This trait is sealed and can't be implemented outside of this crate.
+///
+/// [gnu-plural]: https://www.gnu.org/software/gettext/manual/html_node/Plural-forms.html
+pub trait PluralCount: sealed::Sealed + Copy {
+    #[doc(hidden)]
+    fn to_c_ulong(self) -> c_ulong;
+}
+
+fn count_to_c_ulong(n: u128) -> c_ulong {
+    if n > c_ulong::MAX as u128 {
+        (n % 1_000_000 + 1_000_000) as c_ulong
+    } else {
+        n as c_ulong
+    }
+}
+
+macro_rules! plural_count_impl {
+    ($($unsigned:ty),*; $($signed:ty),*) => {
+        $(
+            impl sealed::Sealed for $unsigned {}
+            impl PluralCount for $unsigned {
+                fn to_c_ulong(self) -> c_ulong {
+                    count_to_c_ulong(self as u128)
+                }
+            }
+        )*
+        $(
+            impl sealed::Sealed for $signed {}
+            impl PluralCount for $signed {
+                fn to_c_ulong(self) -> c_ulong {
+                    count_to_c_ulong((self as i128).unsigned_abs())
+                }
+            }
+        )*
+    };
+}
+
+plural_count_impl!(u8, u16, u32, u64, u128, usize; i8, i16, i32, i64, i128, isize);
+
 /// Translate msgid to localized message from the default domain.
 ///
 /// For more information, see [gettext(3)][].
@@ -217,10 +326,11 @@ where
 /// * `msgid` or `msgid_plural` contain an internal 0 byte, as such values can't be passed to the
 ///     underlying C API;
 /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
-pub fn ngettext<T, S>(msgid: T, msgid_plural: S, n: u32) -> String
+pub fn ngettext<T, S, N>(msgid: T, msgid_plural: S, n: N) -> String
 where
     T: Into<String>,
     S: Into<String>,
+    N: PluralCount,
 {
     let msgid = CString::new(msgid.into()).expect("`msgid` contains an internal 0 byte");
     let msgid_plural =
@@ -229,7 +339,7 @@ where
         CStr::from_ptr(ffi::ngettext(
             msgid.as_ptr(),
             msgid_plural.as_ptr(),
-            n as c_ulong,
+            n.to_c_ulong(),
         ))
         .to_str()
         .expect("ngettext() returned invalid UTF-8")
@@ -249,11 +359,12 @@ where
 /// * `domainname`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such values can't be
 ///     passed to the underlying C API;
 /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
-pub fn dngettext<T, U, V>(domainname: T, msgid: U, msgid_plural: V, n: u32) -> String
+pub fn dngettext<T, U, V, N>(domainname: T, msgid: U, msgid_plural: V, n: N) -> String
 where
     T: Into<String>,
     U: Into<String>,
     V: Into<String>,
+    N: PluralCount,
 {
     let domainname =
         CString::new(domainname.into()).expect("`domainname` contains an internal 0 byte");
@@ -265,7 +376,7 @@ where
             domainname.as_ptr(),
             msgid.as_ptr(),
             msgid_plural.as_ptr(),
-            n as c_ulong,
+            n.to_c_ulong(),
         ))
         .to_str()
         .expect("dngettext() returned invalid UTF-8")
@@ -286,17 +397,18 @@ where
 /// * `domainname`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such values can't be
 ///     passed to the underlying C API;
 /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
-pub fn dcngettext<T, U, V>(
+pub fn dcngettext<T, U, V, N>(
     domainname: T,
     msgid: U,
     msgid_plural: V,
-    n: u32,
+    n: N,
     category: LocaleCategory,
 ) -> String
 where
     T: Into<String>,
     U: Into<String>,
     V: Into<String>,
+    N: PluralCount,
 {
     let domainname =
         CString::new(domainname.into()).expect("`domainname` contains an internal 0 byte");
@@ -308,7 +420,7 @@ where
             domainname.as_ptr(),
             msgid.as_ptr(),
             msgid_plural.as_ptr(),
-            n as c_ulong,
+            n.to_c_ulong(),
             category as i32,
         ))
         .to_str()
@@ -337,7 +449,7 @@ pub fn textdomain<T: Into<Vec<u8>>>(domainname: T) -> Result<Vec<u8>, io::Error>
     unsafe {
         let result = ffi::textdomain(domainname.as_ptr());
         if result.is_null() {
-            Err(io::Error::last_os_error())
+            Err(last_error())
         } else {
             Ok(CStr::from_ptr(result).to_bytes().to_owned())
         }
@@ -356,8 +468,10 @@ pub fn textdomain<T: Into<Vec<u8>>>(domainname: T) -> Result<Vec<u8>, io::Error>
 ///
 /// # Panics
 ///
-/// Panics if `domainname` or `dirname` contain an internal 0 byte, as such values can't be passed
-/// to the underlying C API.
+/// Panics if:
+/// * `domainname` or `dirname` contain an internal 0 byte, as such values can't be passed to the
+///     underlying C API;
+/// * on targets other than Unix and Windows, `dirname` is not in UTF-8.
 pub fn bindtextdomain<T, U>(domainname: T, dirname: U) -> Result<PathBuf, io::Error>
 where
     T: Into<Vec<u8>>,
@@ -380,7 +494,7 @@ where
         unsafe {
             let mut ptr = ffi::wbindtextdomain(domainname.as_ptr(), dirname.as_ptr());
             if ptr.is_null() {
-                Err(io::Error::last_os_error())
+                Err(last_error())
             } else {
                 let mut result = vec![];
                 while *ptr != 0_u16 {
@@ -392,7 +506,7 @@ where
         }
     }
 
-    #[cfg(not(windows))]
+    #[cfg(unix)]
     {
         use std::ffi::OsString;
         use std::os::unix::ffi::OsStringExt;
@@ -402,7 +516,7 @@ where
         unsafe {
             let result = ffi::bindtextdomain(domainname.as_ptr(), dirname.as_ptr());
             if result.is_null() {
-                Err(io::Error::last_os_error())
+                Err(last_error())
             } else {
                 let result = CStr::from_ptr(result);
                 Ok(PathBuf::from(OsString::from_vec(
@@ -411,6 +525,22 @@ where
             }
         }
     }
+
+    // Other targets (e.g. wasm32) have no way to get raw bytes out of a path, so we require UTF-8.
+    #[cfg(not(any(unix, windows)))]
+    {
+        let dirname = dirname.into_string().expect("`dirname` is not valid UTF-8");
+        let dirname = CString::new(dirname).expect("`dirname` contains an internal 0 byte");
+        unsafe {
+            let result = ffi::bindtextdomain(domainname.as_ptr(), dirname.as_ptr());
+            if result.is_null() {
+                Err(last_error())
+            } else {
+                let result = CStr::from_ptr(result);
+                Ok(PathBuf::from(result.to_string_lossy().into_owned()))
+            }
+        }
+    }
 }
 
 /// Set current locale.
@@ -467,7 +597,7 @@ where
     unsafe {
         let result = ffi::bind_textdomain_codeset(domainname.as_ptr(), codeset.as_ptr());
         if result.is_null() {
-            let error = io::Error::last_os_error();
+            let error = last_error();
             if let Some(0) = error.raw_os_error() {
                 return Ok(None);
             } else {
@@ -483,6 +613,21 @@ where
     }
 }
 
+/// Returns the error that made the last call to libintl return `NULL`.
+fn last_error() -> io::Error {
+    #[cfg(not(feature = "pure-rust"))]
+    {
+        io::Error::last_os_error()
+    }
+
+    // The pure-Rust backend doesn't set `errno`; it only returns `NULL` for settings that aren't
+    // set.
+    #[cfg(feature = "pure-rust")]
+    {
+        io::Error::from_raw_os_error(0)
+    }
+}
+
 static CONTEXT_SEPARATOR: char = '\x04';
 
 fn build_context_id(ctxt: &str, msgid: &str) -> String {
@@ -531,11 +676,12 @@ where
 /// * `msgctxt`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such values can't be
 ///     passed to the underlying C API;
 /// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
-pub fn npgettext<T, U, V>(msgctxt: T, msgid: U, msgid_plural: V, n: u32) -> String
+pub fn npgettext<T, U, V, N>(msgctxt: T, msgid: U, msgid_plural: V, n: N) -> String
 where
     T: Into<String>,
     U: Into<String>,
     V: Into<String>,
+    N: PluralCount,
 {
     let msgctxt = msgctxt.into();
     panic_on_zero_in_ctxt(&msgctxt);
@@ -553,10 +699,166 @@ where
     translation
 }
 
+/// Translate msgid to localized message from the specified domain (with context support).
+///
+/// # Panics
+///
+/// Panics if:
+/// * `domainname`, `msgctxt`, or `msgid` contain an internal 0 byte, as such values can't be
+///   passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+pub fn dpgettext<T, U, V>(domainname: T, msgctxt: U, msgid: V) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    panic_on_zero_in_ctxt(&msgctxt);
+
+    let msgid = msgid.into();
+    let text = build_context_id(&msgctxt, &msgid);
+
+    let translation = dgettext(domainname.clone(), text);
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dgettext(domainname, msgid);
+    }
+
+    translation
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category
+/// (with context support).
+///
+/// # Panics
+///
+/// Panics if:
+/// * `domainname`, `msgctxt`, or `msgid` contain an internal 0 byte, as such values can't be
+///   passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+pub fn dcpgettext<T, U, V>(domainname: T, msgctxt: U, msgid: V, category: LocaleCategory) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    panic_on_zero_in_ctxt(&msgctxt);
+
+    let msgid = msgid.into();
+    let text = build_context_id(&msgctxt, &msgid);
+
+    let translation = dcgettext(domainname.clone(), text, category);
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dcgettext(domainname, msgid, category);
+    }
+
+    translation
+}
+
+/// Translate msgid to localized message from the specified domain (with plural support and
+/// context support).
+///
+/// # Panics
+///
+/// Panics if:
+/// * `domainname`, `msgctxt`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such
+///   values can't be passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+pub fn dnpgettext<T, U, V, W, N>(
+    domainname: T,
+    msgctxt: U,
+    msgid: V,
+    msgid_plural: W,
+    n: N,
+) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    W: Into<String>,
+    N: PluralCount,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    panic_on_zero_in_ctxt(&msgctxt);
+
+    let singular_msgid = msgid.into();
+    let plural_msgid = msgid_plural.into();
+    let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+    let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+    let translation = dngettext(domainname.clone(), singular_ctxt, plural_ctxt, n);
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dngettext(domainname, singular_msgid, plural_msgid, n);
+    }
+
+    translation
+}
+
+/// Translate msgid to localized message from the specified domain using custom locale category
+/// (with plural support and context support).
+///
+/// # Panics
+///
+/// Panics if:
+/// * `domainname`, `msgctxt`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such
+///   values can't be passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+pub fn dcnpgettext<T, U, V, W, N>(
+    domainname: T,
+    msgctxt: U,
+    msgid: V,
+    msgid_plural: W,
+    n: N,
+    category: LocaleCategory,
+) -> String
+where
+    T: Into<String>,
+    U: Into<String>,
+    V: Into<String>,
+    W: Into<String>,
+    N: PluralCount,
+{
+    let domainname = domainname.into();
+    let msgctxt = msgctxt.into();
+    panic_on_zero_in_ctxt(&msgctxt);
+
+    let singular_msgid = msgid.into();
+    let plural_msgid = msgid_plural.into();
+    let singular_ctxt = build_context_id(&msgctxt, &singular_msgid);
+    let plural_ctxt = build_context_id(&msgctxt, &plural_msgid);
+
+    let translation = dcngettext(domainname.clone(), singular_ctxt, plural_ctxt, n, category);
+    if translation.contains(CONTEXT_SEPARATOR) {
+        return dcngettext(domainname, singular_msgid, plural_msgid, n, category);
+    }
+
+    translation
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn plural_counts_fit_into_c_ulong() {
+        assert_eq!(1u8.to_c_ulong(), 1);
+        assert_eq!(42usize.to_c_ulong(), 42);
+        assert_eq!((-3i32).to_c_ulong(), 3);
+        assert_eq!(i64::MIN.to_c_ulong(), count_to_c_ulong(1 << 63));
+        assert_eq!(u128::MAX.to_c_ulong(), 1_211_455);
+
+        if std::mem::size_of::<c_ulong>() == 4 {
+            assert_eq!(4_294_967_297u64.to_c_ulong(), 1_967_297);
+            assert_eq!(5_000_000_000u64.to_c_ulong(), 1_000_000);
+        } else {
+            assert_eq!(4_294_967_297u64.to_c_ulong(), 4_294_967_297);
+        }
+    }
+
     #[test]
     fn smoke_test() {
         setlocale(LocaleCategory::LcAll, "en_US.UTF-8");
@@ -760,4 +1062,88 @@ mod tests {
     fn npgettext_panics_on_zero_in_msgid_plural() {
         npgettext("context", "uno", "one \0fewer", 10585);
     }
+
+    #[test]
+    #[should_panic(expected = "`domainname` contains an internal 0 byte")]
+    fn dpgettext_panics_on_zero_in_domainname() {
+        dpgettext("dom\0ain", "ctx", "string");
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgctxt` contains an internal 0 byte")]
+    fn dpgettext_panics_on_zero_in_msgctxt() {
+        dpgettext("domain", "c\0tx", "string");
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid` contains an internal 0 byte")]
+    fn dpgettext_panics_on_zero_in_msgid() {
+        dpgettext("domain", "ctx", "str\0ing");
+    }
+
+    #[test]
+    #[should_panic(expected = "`domainname` contains an internal 0 byte")]
+    fn dcpgettext_panics_on_zero_in_domainname() {
+        dcpgettext("\0domain", "ctx", "string", LocaleCategory::LcTime);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgctxt` contains an internal 0 byte")]
+    fn dcpgettext_panics_on_zero_in_msgctxt() {
+        dcpgettext("domain", "ctx\0", "string", LocaleCategory::LcMessages);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid` contains an internal 0 byte")]
+    fn dcpgettext_panics_on_zero_in_msgid() {
+        dcpgettext("domain", "ctx", "\0", LocaleCategory::LcAll);
+    }
+
+    #[test]
+    #[should_panic(expected = "`domainname` contains an internal 0 byte")]
+    fn dnpgettext_panics_on_zero_in_domainname() {
+        dnpgettext("do\0main", "ctx", "singular", "plural", 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgctxt` contains an internal 0 byte")]
+    fn dnpgettext_panics_on_zero_in_msgctxt() {
+        dnpgettext("domain", "\0", "singular", "plural", 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid` contains an internal 0 byte")]
+    fn dnpgettext_panics_on_zero_in_msgid() {
+        dnpgettext("domain", "ctx", "sin\0gular", "plural", 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid_plural` contains an internal 0 byte")]
+    fn dnpgettext_panics_on_zero_in_msgid_plural() {
+        dnpgettext("domain", "ctx", "singular", "plu\0ral", 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "`domainname` contains an internal 0 byte")]
+    fn dcnpgettext_panics_on_zero_in_domainname() {
+        dcnpgettext("d\0", "ctx", "one", "many", 5, LocaleCategory::LcMessages);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgctxt` contains an internal 0 byte")]
+    fn dcnpgettext_panics_on_zero_in_msgctxt() {
+        dcnpgettext("d", "c\0tx", "one", "many", 6, LocaleCategory::LcMessages);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid` contains an internal 0 byte")]
+    fn dcnpgettext_panics_on_zero_in_msgid() {
+        dcnpgettext("d", "ctx", "o\0ne", "many", 7, LocaleCategory::LcMessages);
+    }
+
+    #[test]
+    #[should_panic(expected = "`msgid_plural` contains an internal 0 byte")]
+    fn dcnpgettext_panics_on_zero_in_msgid_plural() {
+        dcnpgettext("d", "ctx", "one", "ma\0ny", 8, LocaleCategory::LcMessages);
+    }
 }
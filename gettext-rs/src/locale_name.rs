@@ -0,0 +1,182 @@
+//! Handling of locale names and the environment variables that select them, shared by
+//! [`TextDomain`][::TextDomain] and the pure-Rust backend.
+
+use std::env;
+
+pub fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Resolves the locale for the category with the given name (e.g. `LC_MESSAGES`) the same way
+/// `setlocale(category, "")` does. Returns `None` if none of the variables are set, in which case
+/// the C library uses the "C" locale.
+pub fn locale_from_env(category: &str) -> Option<String> {
+    non_empty_var("LC_ALL")
+        .or_else(|| non_empty_var(category))
+        .or_else(|| non_empty_var("LANG"))
+}
+
+/// Returns the languages to search translations for, given the name of the current locale and the
+/// value of the `LANGUAGE` environment variable.
+///
+/// Like GNU gettext, `LANGUAGE` takes precedence over the locale, unless the locale is "C". The
+/// list is cut short at the first "C" entry, since that locale doesn't have translations.
+pub fn languages(locale: &str, language_var: Option<String>) -> Vec<String> {
+    if locale == "C" || locale == "POSIX" {
+        return vec![];
+    }
+
+    let list = language_var.unwrap_or_else(|| locale.to_owned());
+    let mut languages: Vec<String> = vec![];
+    for language in list
+        .split(':')
+        .filter(|language| !language.is_empty())
+        .take_while(|&language| language != "C" && language != "POSIX")
+    {
+        if !languages.iter().any(|other| other == language) {
+            languages.push(language.to_owned());
+        }
+    }
+    languages
+}
+
+/// Converts a BCP 47 language tag like `fr-FR` into the form used for locale names and the
+/// directories of a `locale` directory, i.e. `fr_FR`. Script and other subtags are dropped, as
+/// they have no equivalent there. Names that are already in that form are returned unchanged.
+pub fn xpg_locale_name(name: &str) -> String {
+    if !name.contains('-') || name.contains('_') {
+        return name.to_owned();
+    }
+
+    let mut subtags = name.split('-');
+    let mut result = subtags.next().unwrap_or("").to_owned();
+    let territory = subtags.find(|subtag| {
+        (subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+            || (subtag.len() == 3 && subtag.chars().all(|c| c.is_ascii_digit()))
+    });
+    if let Some(territory) = territory {
+        result.push('_');
+        result.push_str(&territory.to_ascii_uppercase());
+    }
+    result
+}
+
+/// Returns the language part of a locale name, e.g. `pt` for `pt_BR.UTF-8`.
+pub fn language_part(name: &str) -> &str {
+    name.split(&['_', '-', '.', '@'][..]).next().unwrap_or("")
+}
+
+/// Returns the variants of an XPG locale name (`language[_territory][.codeset][@modifier]`) in the
+/// order in which GNU gettext looks them up: from the most specific to the language alone.
+pub fn locale_variants(locale: &str) -> Vec<String> {
+    let (rest, modifier) = split_off(locale, '@');
+    let (rest, codeset) = split_off(rest, '.');
+    let (language, territory) = split_off(rest, '_');
+    let normalized_codeset = codeset
+        .map(normalize_codeset)
+        .filter(|normalized| Some(normalized.as_str()) != codeset);
+
+    let mut variants = vec![];
+    for &modifier in &[modifier, None] {
+        for &territory in &[territory, None] {
+            for codeset in &[codeset, normalized_codeset.as_deref(), None] {
+                let mut variant = language.to_owned();
+                if let Some(territory) = territory {
+                    variant.push('_');
+                    variant.push_str(territory);
+                }
+                if let Some(codeset) = codeset {
+                    variant.push('.');
+                    variant.push_str(codeset);
+                }
+                if let Some(modifier) = modifier {
+                    variant.push('@');
+                    variant.push_str(modifier);
+                }
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+    }
+    variants
+}
+
+fn split_off(string: &str, separator: char) -> (&str, Option<&str>) {
+    match string.find(separator) {
+        Some(position) => (&string[..position], Some(&string[position + 1..])),
+        None => (string, None),
+    }
+}
+
+/// Normalizes codeset name the same way GNU gettext does: only letters and digits are kept,
+/// letters are lowercased, and names consisting only of digits are prefixed with "iso".
+fn normalize_codeset(codeset: &str) -> String {
+    let normalized: String = codeset
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    if normalized.chars().all(|c| c.is_ascii_digit()) {
+        format!("iso{}", normalized)
+    } else {
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_come_from_language_variable() {
+        assert_eq!(languages("pt_BR.UTF-8", None), vec!["pt_BR.UTF-8"]);
+        assert_eq!(
+            languages("pt_BR.UTF-8", Some("pt_BR:pt::es:pt".to_owned())),
+            vec!["pt_BR", "pt", "es"]
+        );
+        assert_eq!(languages("de_DE", Some("fr:C:es".to_owned())), vec!["fr"]);
+        assert!(languages("C", Some("fr".to_owned())).is_empty());
+    }
+
+    #[test]
+    fn language_tags_are_converted_to_locale_names() {
+        assert_eq!(xpg_locale_name("fr-FR"), "fr_FR");
+        assert_eq!(xpg_locale_name("zh-Hans-cn"), "zh_CN");
+        assert_eq!(xpg_locale_name("es-419"), "es_419");
+        assert_eq!(xpg_locale_name("de"), "de");
+        assert_eq!(xpg_locale_name("sr_RS.UTF-8@latin"), "sr_RS.UTF-8@latin");
+    }
+
+    #[test]
+    fn language_part_is_extracted() {
+        assert_eq!(language_part("pt_BR.UTF-8"), "pt");
+        assert_eq!(language_part("sr@latin"), "sr");
+        assert_eq!(language_part("fr-FR"), "fr");
+        assert_eq!(language_part("de"), "de");
+    }
+
+    #[test]
+    fn locale_variants_follow_gnu_order() {
+        assert_eq!(
+            locale_variants("de_DE.UTF-8@euro"),
+            vec![
+                "de_DE.UTF-8@euro",
+                "de_DE.utf8@euro",
+                "de_DE@euro",
+                "de.UTF-8@euro",
+                "de.utf8@euro",
+                "de@euro",
+                "de_DE.UTF-8",
+                "de_DE.utf8",
+                "de_DE",
+                "de.UTF-8",
+                "de.utf8",
+                "de",
+            ]
+        );
+        assert_eq!(locale_variants("sr@latin"), vec!["sr@latin", "sr"]);
+        assert_eq!(locale_variants("pt_BR"), vec!["pt_BR", "pt"]);
+        assert_eq!(normalize_codeset("8859-1"), "iso88591");
+    }
+}
@@ -0,0 +1,417 @@
+//! Implementation details of [`gettext!`], [`ngettext!`] and [`pgettext!`] macros.
+//!
+//! This module also defines `gettext_fmt!` and other formatting macros, which are implemented in
+//! the [`formatting`][::formatting] module.
+//!
+//! The first three macros append the terminating 0 byte to their literals and turn them into
+//! C strings during constant evaluation, which fails if the literals contain any other 0 bytes.
+//! The C strings are then passed to the functions of the [`borrowed`][::borrowed] module, which
+//! hand them to libintl as they are, without copying them.
+//!
+//! [`gettext!`]: ../macro.gettext.html
+//! [`ngettext!`]: ../macro.ngettext.html
+//! [`pgettext!`]: ../macro.pgettext.html
+
+use std::ffi::CStr;
+
+/// Translate a string literal to localized message from the default domain.
+///
+/// This is like [`gettext`][::gettext()], but the literal is turned into a C string at compile
+/// time, so there is nothing to allocate at runtime, and only the result is checked for UTF-8.
+/// The result is either the literal itself, or a string from the catalog, which stays valid until
+/// the end of the program (see [this note](borrowed/index.html#when-the-results-change)).
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     let greeting: &'static str = gettext!("Hello, world!");
+///     println!("{}", greeting);
+/// }
+/// ```
+///
+/// A literal that contains a 0 byte doesn't compile:
+///
+/// ```rust,compile_fail
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     gettext!("Hello,\0world!");
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+#[macro_export]
+macro_rules! gettext {
+    ($msgid:literal $(,)?) => {{
+        const MSGID: &::std::ffi::CStr = $crate::macros::cstr(concat!($msgid, "\0"));
+        $crate::borrowed::gettext(MSGID)
+    }};
+}
+
+/// Translate string literals to localized message from the default domain (with plural support).
+///
+/// This is like [`ngettext`][::ngettext()], but the literals are turned into C strings at compile
+/// time; see [`gettext!`][::gettext!] for details.
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     let files = 3;
+///     println!("{}", ngettext!("One file", "Many files", files));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+#[macro_export]
+macro_rules! ngettext {
+    ($msgid:literal, $msgid_plural:literal, $n:expr $(,)?) => {{
+        const MSGID: &::std::ffi::CStr = $crate::macros::cstr(concat!($msgid, "\0"));
+        const MSGID_PLURAL: &::std::ffi::CStr = $crate::macros::cstr(concat!($msgid_plural, "\0"));
+        $crate::borrowed::ngettext(MSGID, MSGID_PLURAL, $n)
+    }};
+}
+
+/// Translate a string literal to localized message from the default domain (with context
+/// support).
+///
+/// This is like [`pgettext`][::pgettext()], but the literals are turned into C strings at compile
+/// time; see [`gettext!`][::gettext!] for details.
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     println!("{}", pgettext!("menu", "Open"));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required)).
+#[macro_export]
+macro_rules! pgettext {
+    ($msgctxt:literal, $msgid:literal $(,)?) => {{
+        const CONTEXT_ID: &::std::ffi::CStr =
+            $crate::macros::cstr(concat!($msgctxt, "\x04", $msgid, "\0"));
+        const MSGID: &::std::ffi::CStr = $crate::macros::cstr(concat!($msgid, "\0"));
+        $crate::borrowed::pgettext(CONTEXT_ID, MSGID)
+    }};
+}
+
+/// Create a [`Domain`][::Domain] from a string literal at compile time.
+///
+/// The literal is turned into a C string at compile time, so the result can be stored in a
+/// `static`. The locale category to look messages up in can be passed as well (see
+/// [`Domain::with_category`][::Domain::with_category]):
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// use gettextrs::{Domain, LocaleCategory};
+///
+/// static DOMAIN: Domain = domain!("my_library");
+/// static TIME_DOMAIN: Domain = domain!("my_library", LocaleCategory::LcTime);
+///
+/// fn main() {
+///     println!("{}", DOMAIN.gettext("Hello, world!"));
+/// }
+/// ```
+///
+/// A literal that contains a 0 byte doesn't compile:
+///
+/// ```rust,compile_fail
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// static DOMAIN: gettextrs::Domain = domain!("my\0library");
+///
+/// fn main() {}
+/// ```
+#[macro_export]
+macro_rules! domain {
+    ($domainname:literal $(,)?) => {
+        $crate::domain!(@category $domainname, None)
+    };
+    ($domainname:literal, $category:expr $(,)?) => {
+        $crate::domain!(@category $domainname, Some($category))
+    };
+    (@category $domainname:literal, $category:expr) => {{
+        const DOMAINNAME: &::std::ffi::CStr = $crate::macros::cstr(concat!($domainname, "\0"));
+        $crate::Domain::from_static(DOMAINNAME, $category)
+    }};
+}
+
+/// Translate msgid to localized message from the default domain, and substitute arguments into
+/// it.
+///
+/// The arguments are passed like to `format!`: positional ones first, then named ones. They're
+/// substituted into `{}`, `{0}` and `{name}` placeholders; see the [`formatting`][::formatting]
+/// module for details, including what happens if the translation is broken.
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     let user = "Alice";
+///     println!("{}", gettext_fmt!("Hello, {name}!", name = user));
+///     println!("{}", gettext_fmt!("{} says {}", user, "hi"));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if:
+/// * `msgid` contains an internal 0 byte, as such values can't be passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required));
+/// * the arguments can't be substituted into `msgid`.
+#[macro_export]
+macro_rules! gettext_fmt {
+    ($msgid:expr $(, $($arguments:tt)*)?) => {{
+        let msgid: &str = &$msgid;
+        $crate::formatting::format_translation(
+            &$crate::gettext(msgid),
+            msgid,
+            &$crate::__gettext_fmt_arguments!([] $($($arguments)*)?),
+            true,
+        )
+    }};
+}
+
+/// Translate msgid to localized message from the default domain (with plural support), and
+/// substitute arguments into it.
+///
+/// Arguments follow the count; they're handled like in [`gettext_fmt!`][::gettext_fmt!]. The
+/// count is not substituted automatically, so pass it as an argument if the message mentions it.
+///
+/// ```rust,no_run
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// fn main() {
+///     let count = 3;
+///     println!("{}", ngettext_fmt!("One file", "{n} files", count, n = count));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if:
+/// * `msgid` or `msgid_plural` contain an internal 0 byte, as such values can't be passed to the
+///   underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required));
+/// * the arguments can't be substituted into `msgid` or `msgid_plural`, whichever is used if
+///   there's no translation.
+#[macro_export]
+macro_rules! ngettext_fmt {
+    ($msgid:expr, $msgid_plural:expr, $n:expr $(, $($arguments:tt)*)?) => {{
+        let msgid: &str = &$msgid;
+        let msgid_plural: &str = &$msgid_plural;
+        let n = $n;
+        $crate::formatting::format_plural_translation(
+            &$crate::ngettext(msgid, msgid_plural, n),
+            msgid,
+            msgid_plural,
+            n,
+            &$crate::__gettext_fmt_arguments!([] $($($arguments)*)?),
+        )
+    }};
+}
+
+/// Translate msgid to localized message from the default domain (with context support), and
+/// substitute arguments into it.
+///
+/// Arguments are handled like in [`gettext_fmt!`][::gettext_fmt!].
+///
+/// # Panics
+///
+/// Panics if:
+/// * `msgctxt` or `msgid` contain an internal 0 byte, as such values can't be passed to the
+///   underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required));
+/// * the arguments can't be substituted into `msgid`.
+#[macro_export]
+macro_rules! pgettext_fmt {
+    ($msgctxt:expr, $msgid:expr $(, $($arguments:tt)*)?) => {{
+        let msgid: &str = &$msgid;
+        $crate::formatting::format_translation(
+            &$crate::pgettext($msgctxt, msgid),
+            msgid,
+            &$crate::__gettext_fmt_arguments!([] $($($arguments)*)?),
+            true,
+        )
+    }};
+}
+
+/// Translate msgid to localized message from the default domain (with plural support and context
+/// support), and substitute arguments into it.
+///
+/// Arguments are handled like in [`ngettext_fmt!`][::ngettext_fmt!].
+///
+/// # Panics
+///
+/// Panics if:
+/// * `msgctxt`, `msgid`, or `msgid_plural` contain an internal 0 byte, as such values can't be
+///   passed to the underlying C API;
+/// * the result is not in UTF-8 (see [this note](./index.html#utf-8-is-required));
+/// * the arguments can't be substituted into `msgid` or `msgid_plural`, whichever is used if
+///   there's no translation.
+#[macro_export]
+macro_rules! npgettext_fmt {
+    ($msgctxt:expr, $msgid:expr, $msgid_plural:expr, $n:expr $(, $($arguments:tt)*)?) => {{
+        let msgid: &str = &$msgid;
+        let msgid_plural: &str = &$msgid_plural;
+        let n = $n;
+        $crate::formatting::format_plural_translation(
+            &$crate::npgettext($msgctxt, msgid, msgid_plural, n),
+            msgid,
+            msgid_plural,
+            n,
+            &$crate::__gettext_fmt_arguments!([] $($($arguments)*)?),
+        )
+    }};
+}
+
+/// Include the list of catalogs that the build script wrote into `OUT_DIR`, as a
+/// `&'static [EmbeddedCatalog]` that can be passed to
+/// [`TextDomain::embedded`][::TextDomain::embedded] or
+/// [`embedded::register`][::embedded::register].
+///
+/// Without arguments, the list is read from `$OUT_DIR/gettext_catalogs.rs`, which is where
+/// `gettext_tools::build::embed` puts it. A path to another file can be passed instead. See the
+/// [`embedded`][::embedded] module for details.
+///
+/// This macro is only available with the `pure-rust` feature.
+///
+/// ```rust,ignore
+/// #[macro_use]
+/// extern crate gettextrs;
+///
+/// use gettextrs::TextDomain;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     TextDomain::new("hellorust")
+///         .embedded(include_catalogs!())
+///         .init()?;
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "pure-rust")]
+#[macro_export]
+macro_rules! include_catalogs {
+    () => {
+        $crate::include_catalogs!(concat!(env!("OUT_DIR"), "/gettext_catalogs.rs"))
+    };
+    ($path:expr $(,)?) => {{
+        use $crate::embedded::EmbeddedCatalog;
+        const CATALOGS: &[EmbeddedCatalog] = include!($path);
+        CATALOGS
+    }};
+}
+
+/// Turns `format!`-style arguments into an array of
+/// [`formatting::Argument`][::formatting::Argument].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __gettext_fmt_arguments {
+    ([$($done:expr,)*]) => {
+        [$($done,)*]
+    };
+    ([$($done:expr,)*] $name:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__gettext_fmt_arguments!(
+            [$($done,)* $crate::formatting::Argument::named(stringify!($name), &$value),]
+            $($($rest)*)?
+        )
+    };
+    ([$($done:expr,)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__gettext_fmt_arguments!(
+            [$($done,)* $crate::formatting::Argument::positional(&$value),]
+            $($($rest)*)?
+        )
+    };
+}
+
+/// Turns `string`, which has to end with the only 0 byte in it, into a C string. The macros call
+/// this in constants, so that a literal with an internal 0 byte fails to compile.
+///
+/// # Panics
+///
+/// Panics if `string` is not 0-terminated, or contains another 0 byte.
+pub const fn cstr(string: &'static str) -> &'static CStr {
+    match CStr::from_bytes_with_nul(string.as_bytes()) {
+        Ok(string) => string,
+        Err(_) => panic!("the literal contains an internal 0 byte, or is not 0-terminated"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn untranslated_literals_are_returned_as_is() {
+        assert_eq!(gettext!("Not translated"), "Not translated");
+        assert_eq!(ngettext!("One file", "Many files", 1), "One file");
+        assert_eq!(ngettext!("One file", "Many files", 2u64), "Many files");
+        assert_eq!(pgettext!("context", "Not translated"), "Not translated");
+        assert_eq!(pgettext!("", "Not translated",), "Not translated");
+    }
+
+    #[test]
+    fn arguments_are_substituted_into_untranslated_messages() {
+        let user = "Alice";
+        let count = 3;
+        assert_eq!(gettext_fmt!("Hello, {name}!", name = user), "Hello, Alice!");
+        assert_eq!(gettext_fmt!("{} and {}", user, "Bob",), "Alice and Bob");
+        assert_eq!(gettext_fmt!(String::from("{{}}")), "{}");
+        assert_eq!(
+            ngettext_fmt!("One file", "{n} files", count, n = count),
+            "3 files"
+        );
+        assert_eq!(
+            ngettext_fmt!("{} has one file", "{} has {n} files", 1u64, user, n = 1),
+            "Alice has one file"
+        );
+        assert_eq!(
+            pgettext_fmt!("greeting", "Hi, {0} and {0}", user),
+            "Hi, Alice and Alice"
+        );
+        assert_eq!(
+            npgettext_fmt!(
+                "files",
+                "One {kind} file",
+                "{n} {kind} files",
+                2,
+                n = 2,
+                kind = "text"
+            ),
+            "2 text files"
+        );
+    }
+
+    #[test]
+    fn literals_are_turned_into_c_strings() {
+        assert_eq!(super::cstr("Hello\0").to_bytes(), b"Hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "internal 0 byte")]
+    fn internal_nul_bytes_are_rejected() {
+        super::cstr("Hello,\0world!\0");
+    }
+
+    #[test]
+    #[should_panic(expected = "internal 0 byte")]
+    fn literals_must_be_nul_terminated() {
+        super::cstr("Hello");
+    }
+}
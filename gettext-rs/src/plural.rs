@@ -0,0 +1,616 @@
+//! Parsing and evaluation of `Plural-Forms` headers.
+//!
+//! Every catalog tells gettext how many plural forms its language has, and how to pick one for a
+//! given number. This is done with a header like this:
+//!
+//! ```text
+//! Plural-Forms: nplurals=3; plural=n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2;
+//! ```
+//!
+//! The expression uses a subset of C: the variable `n`, non-negative integer literals,
+//! parentheses, the ternary operator `?:`, logical operators `!`, `&&` and `||`, comparisons, and
+//! arithmetic operators `+`, `-`, `*`, `/` and `%`. This module parses such expressions and
+//! evaluates them the same way GNU gettext does, so that plural rules can be checked without
+//! going through [`ngettext`][::ngettext()]:
+//!
+//! ```rust
+//! use gettextrs::plural::PluralForms;
+//!
+//! # fn main() -> Result<(), gettextrs::plural::PluralFormsError> {
+//! let polish = PluralForms::parse(
+//!     "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+//! )?;
+//! assert_eq!(polish.nplurals(), 3);
+//! assert_eq!(polish.index(1), 0);
+//! assert_eq!(polish.index(22), 1);
+//! assert_eq!(polish.index(25), 2);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Like in GNU gettext, all values are unsigned, and arithmetic wraps around on overflow.
+//! Comparisons and logical operators evaluate to 1 if true, and to 0 otherwise.
+//!
+//! Expressions can't be nested more than 100 levels deep, counting parentheses and operators, which
+//! is far more than any language needs. Parsing and evaluating expressions is recursive, so this
+//! keeps headers from untrusted catalogs from overflowing the stack.
+
+use std::cmp;
+use std::error;
+use std::fmt;
+
+/// How deeply expressions can be nested; see the module documentation.
+const MAX_DEPTH: usize = 100;
+
+/// Errors that might come up while parsing a `Plural-Forms` header or expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluralFormsError {
+    /// The header doesn't contain a `Plural-Forms` field.
+    MissingHeader,
+    /// The `nplurals=` part is missing.
+    MissingNplurals,
+    /// The value of `nplurals=` isn't a positive integer.
+    InvalidNplurals(String),
+    /// The `plural=` part is missing.
+    MissingPlural,
+    /// The expression contains an unexpected character or token at the given byte offset.
+    UnexpectedToken(usize),
+    /// The expression ends prematurely.
+    UnexpectedEnd,
+    /// The expression is nested more deeply than the parser allows.
+    TooDeeplyNested,
+}
+
+impl fmt::Display for PluralFormsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::PluralFormsError::*;
+
+        match self {
+            MissingHeader => write!(f, "The header doesn't contain Plural-Forms."),
+            MissingNplurals => write!(f, "Plural-Forms doesn't specify nplurals."),
+            InvalidNplurals(value) => write!(f, r#"nplurals value "{}" is invalid."#, value),
+            MissingPlural => write!(f, "Plural-Forms doesn't specify the plural expression."),
+            UnexpectedToken(offset) => write!(
+                f,
+                "Unexpected token at offset {} of the plural expression.",
+                offset
+            ),
+            UnexpectedEnd => write!(f, "The plural expression ends unexpectedly."),
+            TooDeeplyNested => write!(f, "The plural expression is nested too deeply."),
+        }
+    }
+}
+
+impl error::Error for PluralFormsError {}
+
+/// Binary operators that can appear in a plural expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `%`
+    Remainder,
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+    /// `<=`
+    LessOrEqual,
+    /// `>=`
+    GreaterOrEqual,
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+}
+
+/// A parsed plural expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    /// The variable `n`.
+    N,
+    /// An integer literal.
+    Number(u64),
+    /// Logical negation, `!expr`.
+    Not(Box<Expression>),
+    /// A binary operation.
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    /// The ternary operator, `condition ? if_true : if_false`.
+    Ternary(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses a plural expression, e.g. `n != 1`.
+    pub fn parse(expression: &str) -> Result<Expression, PluralFormsError> {
+        let mut parser = Parser {
+            input: expression.as_bytes(),
+            position: 0,
+            nesting: 0,
+        };
+        let (result, _) = parser.ternary()?;
+        match parser.peek() {
+            None => Ok(result),
+            Some(_) => Err(PluralFormsError::UnexpectedToken(parser.position)),
+        }
+    }
+
+    /// Evaluates the expression for the given `n`.
+    ///
+    /// Like in GNU gettext, a division or remainder by zero evaluates to 0, and the rest of the
+    /// expression is evaluated with that value. The branches of `?:`, and the right-hand sides of
+    /// `&&` and `||`, are only evaluated when needed, just like in C.
+    pub fn evaluate(&self, n: u64) -> u64 {
+        use self::BinaryOperator::*;
+
+        match self {
+            Expression::N => n,
+            Expression::Number(value) => *value,
+            Expression::Not(operand) => (operand.evaluate(n) == 0) as u64,
+            Expression::Ternary(condition, if_true, if_false) => {
+                if condition.evaluate(n) != 0 {
+                    if_true.evaluate(n)
+                } else {
+                    if_false.evaluate(n)
+                }
+            }
+            Expression::Binary(And, left, right) => {
+                (left.evaluate(n) != 0 && right.evaluate(n) != 0) as u64
+            }
+            Expression::Binary(Or, left, right) => {
+                (left.evaluate(n) != 0 || right.evaluate(n) != 0) as u64
+            }
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate(n);
+                let right = right.evaluate(n);
+                match operator {
+                    Multiply => left.wrapping_mul(right),
+                    Divide => left.checked_div(right).unwrap_or(0),
+                    Remainder => left.checked_rem(right).unwrap_or(0),
+                    Add => left.wrapping_add(right),
+                    Subtract => left.wrapping_sub(right),
+                    Less => (left < right) as u64,
+                    Greater => (left > right) as u64,
+                    LessOrEqual => (left <= right) as u64,
+                    GreaterOrEqual => (left >= right) as u64,
+                    Equal => (left == right) as u64,
+                    NotEqual => (left != right) as u64,
+                    And | Or => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+/// The contents of a `Plural-Forms` header: the number of plural forms, and the expression that
+/// picks one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluralForms {
+    nplurals: usize,
+    plural: Expression,
+}
+
+impl Default for PluralForms {
+    /// Returns the rule gettext uses when a catalog doesn't specify one: `nplurals=2; plural=n !=
+    /// 1;`.
+    fn default() -> PluralForms {
+        PluralForms {
+            nplurals: 2,
+            plural: Expression::Binary(
+                BinaryOperator::NotEqual,
+                Box::new(Expression::N),
+                Box::new(Expression::Number(1)),
+            ),
+        }
+    }
+}
+
+impl PluralForms {
+    /// Parses the value of a `Plural-Forms` header, e.g. `nplurals=2; plural=n != 1;`.
+    pub fn parse(value: &str) -> Result<PluralForms, PluralFormsError> {
+        let mut nplurals = None;
+        let mut plural = None;
+        for field in value.split(';') {
+            let mut parts = field.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let value = match parts.next() {
+                Some(value) => value.trim(),
+                None => continue,
+            };
+            match name {
+                "nplurals" => nplurals = Some(value),
+                "plural" => plural = Some(value),
+                _ => {}
+            }
+        }
+
+        let nplurals = nplurals.ok_or(PluralFormsError::MissingNplurals)?;
+        let nplurals = match nplurals.parse() {
+            Ok(count) if count > 0 => count,
+            _ => return Err(PluralFormsError::InvalidNplurals(nplurals.to_owned())),
+        };
+        let plural = Expression::parse(plural.ok_or(PluralFormsError::MissingPlural)?)?;
+
+        Ok(PluralForms { nplurals, plural })
+    }
+
+    /// Finds the `Plural-Forms` field in a catalog header (the translation of the empty string)
+    /// and parses its value.
+    pub fn from_header(header: &str) -> Result<PluralForms, PluralFormsError> {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix("Plural-Forms:"))
+            .ok_or(PluralFormsError::MissingHeader)
+            .and_then(PluralForms::parse)
+    }
+
+    /// Returns the number of plural forms.
+    pub fn nplurals(&self) -> usize {
+        self.nplurals
+    }
+
+    /// Returns the expression that picks the plural form.
+    pub fn expression(&self) -> &Expression {
+        &self.plural
+    }
+
+    /// Returns the index of the plural form to use for `n`.
+    ///
+    /// Like GNU gettext, this returns 0 if the expression evaluates to a value not less than
+    /// [`nplurals`][PluralForms::nplurals].
+    pub fn index(&self, n: u64) -> usize {
+        match self.plural.evaluate(n) {
+            index if index < self.nplurals as u64 => index as usize,
+            _ => 0,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// How many calls to `ternary` and `unary` for `!` are in progress.
+    nesting: usize,
+}
+
+/// A parsed expression, along with the depth of its tree.
+type Parsed = (Expression, usize);
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') =
+            self.input.get(self.position)
+        {
+            self.position += 1;
+        }
+        self.input.get(self.position).cloned()
+    }
+
+    /// Consumes `token` if the input continues with it.
+    fn eat(&mut self, token: &str) -> bool {
+        if self.peek().is_none() || !self.input[self.position..].starts_with(token.as_bytes()) {
+            return false;
+        }
+        // `<` and `!` shouldn't match the beginning of `<=` and `!=`.
+        if token.len() == 1
+            && b"<>!=".contains(&token.as_bytes()[0])
+            && self.input.get(self.position + 1) == Some(&b'=')
+        {
+            return false;
+        }
+
+        self.position += token.len();
+        true
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), PluralFormsError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Guards a recursive call, so that parsing fails before it overflows the stack.
+    fn enter(&mut self) -> Result<(), PluralFormsError> {
+        self.nesting += 1;
+        if self.nesting > MAX_DEPTH {
+            return Err(PluralFormsError::TooDeeplyNested);
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.nesting -= 1;
+    }
+
+    /// Checks the depth of a node whose deepest child has the depth `child_depth`, since
+    /// evaluating and dropping the expression recurse too.
+    fn node_depth(child_depth: usize) -> Result<usize, PluralFormsError> {
+        if child_depth >= MAX_DEPTH {
+            return Err(PluralFormsError::TooDeeplyNested);
+        }
+        Ok(child_depth + 1)
+    }
+
+    fn unexpected(&mut self) -> PluralFormsError {
+        match self.peek() {
+            Some(_) => PluralFormsError::UnexpectedToken(self.position),
+            None => PluralFormsError::UnexpectedEnd,
+        }
+    }
+
+    /// Parses `?:`, which has the lowest precedence and associates to the right.
+    fn ternary(&mut self) -> Result<Parsed, PluralFormsError> {
+        self.enter()?;
+        let (condition, condition_depth) = self.binary(0)?;
+        if !self.eat("?") {
+            self.leave();
+            return Ok((condition, condition_depth));
+        }
+
+        let (if_true, if_true_depth) = self.ternary()?;
+        self.expect(":")?;
+        let (if_false, if_false_depth) = self.ternary()?;
+        self.leave();
+        let depth = cmp::max(condition_depth, cmp::max(if_true_depth, if_false_depth));
+        Ok((
+            Expression::Ternary(Box::new(condition), Box::new(if_true), Box::new(if_false)),
+            Parser::node_depth(depth)?,
+        ))
+    }
+
+    /// Parses left-associative binary operators, starting from the given precedence level.
+    fn binary(&mut self, level: usize) -> Result<Parsed, PluralFormsError> {
+        use self::BinaryOperator::*;
+
+        const LEVELS: &[&[(&str, BinaryOperator)]] = &[
+            &[("||", Or)],
+            &[("&&", And)],
+            &[("==", Equal), ("!=", NotEqual)],
+            &[
+                ("<=", LessOrEqual),
+                (">=", GreaterOrEqual),
+                ("<", Less),
+                (">", Greater),
+            ],
+            &[("+", Add), ("-", Subtract)],
+            &[("*", Multiply), ("/", Divide), ("%", Remainder)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let (mut left, mut depth) = self.binary(level + 1)?;
+        'operators: loop {
+            for &(token, operator) in LEVELS[level] {
+                if self.eat(token) {
+                    let (right, right_depth) = self.binary(level + 1)?;
+                    left = Expression::Binary(operator, Box::new(left), Box::new(right));
+                    depth = Parser::node_depth(cmp::max(depth, right_depth))?;
+                    continue 'operators;
+                }
+            }
+
+            return Ok((left, depth));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Parsed, PluralFormsError> {
+        if self.eat("!") {
+            self.enter()?;
+            let (operand, depth) = self.unary()?;
+            self.leave();
+            return Ok((
+                Expression::Not(Box::new(operand)),
+                Parser::node_depth(depth)?,
+            ));
+        }
+
+        match self.peek() {
+            Some(b'n') => {
+                self.position += 1;
+                Ok((Expression::N, 1))
+            }
+            Some(b'(') => {
+                self.position += 1;
+                let inner = self.ternary()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(digit) if digit.is_ascii_digit() => {
+                let mut value: u64 = 0;
+                while let Some(&digit) = self.input.get(self.position) {
+                    if !digit.is_ascii_digit() {
+                        break;
+                    }
+                    value = value.wrapping_mul(10).wrapping_add(u64::from(digit - b'0'));
+                    self.position += 1;
+                }
+                Ok((Expression::Number(value), 1))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(rule: &str, numbers: &[u64]) -> Vec<usize> {
+        let forms = PluralForms::parse(rule).unwrap();
+        numbers.iter().map(|&n| forms.index(n)).collect()
+    }
+
+    #[test]
+    fn common_rules() {
+        let numbers = [0, 1, 2, 3, 5, 11, 12, 21, 22, 25, 101, 102, 111, 1000];
+
+        assert_eq!(
+            indices("nplurals=2; plural=n != 1;", &numbers),
+            [1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
+        );
+        assert_eq!(indices("nplurals=1; plural=0;", &numbers), [0; 14]);
+        assert_eq!(
+            indices(
+                "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && \
+                 (n%100<10 || n%100>=20) ? 1 : 2);",
+                &numbers
+            ),
+            // Russian
+            [2, 0, 1, 1, 2, 2, 2, 0, 1, 2, 0, 1, 2, 2]
+        );
+        assert_eq!(
+            indices(
+                "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && \
+                 (n%100<10 || n%100>=20) ? 1 : 2);",
+                &numbers
+            ),
+            // Polish
+            [2, 0, 1, 1, 2, 2, 2, 2, 1, 2, 2, 1, 2, 2]
+        );
+        assert_eq!(
+            indices(
+                "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 \
+                 : n%100>=11 ? 4 : 5);",
+                &numbers
+            ),
+            // Arabic
+            [0, 1, 2, 3, 3, 4, 4, 4, 4, 4, 5, 5, 4, 5]
+        );
+    }
+
+    #[test]
+    fn operators_follow_c_precedence() {
+        let parse = |expression| Expression::parse(expression).unwrap();
+
+        assert_eq!(parse("1 + 2 * 3").evaluate(0), 7);
+        assert_eq!(parse("(1 + 2) * 3").evaluate(0), 9);
+        assert_eq!(parse("10 - 4 - 3").evaluate(0), 3);
+        assert_eq!(parse("n % 10 == 1 && n != 11").evaluate(21), 1);
+        assert_eq!(parse("0 || 1 && 0").evaluate(0), 0);
+        assert_eq!(parse("1 < 2 == 1").evaluate(0), 1);
+        assert_eq!(parse("!n").evaluate(0), 1);
+        assert_eq!(parse("!n == 0").evaluate(5), 1);
+        assert_eq!(parse("n ? 1 : n ? 2 : 3").evaluate(0), 3);
+        assert_eq!(parse("n ? n ? 1 : 2 : 3").evaluate(1), 1);
+        assert_eq!(parse("n >= 2 || n <= 0").evaluate(1), 0);
+    }
+
+    #[test]
+    fn arithmetic_is_unsigned_and_wraps() {
+        let parse = |expression| Expression::parse(expression).unwrap();
+
+        assert_eq!(parse("n - 2").evaluate(1), u64::MAX);
+        assert_eq!(parse("n * 2").evaluate(u64::MAX), u64::MAX - 1);
+        assert_eq!(parse("n + 1").evaluate(u64::MAX), 0);
+        assert_eq!(parse("n / 0").evaluate(1), 0);
+        assert_eq!(parse("n % (n - 1)").evaluate(1), 0);
+        assert_eq!(parse("n == 0 || 1 / n").evaluate(0), 1);
+        assert_eq!(parse("n ? 1 / n : 2").evaluate(0), 2);
+
+        let forms = PluralForms::parse("nplurals=2; plural=n - 1;").unwrap();
+        assert_eq!(forms.index(0), 0);
+        assert_eq!(forms.index(2), 1);
+        assert_eq!(forms.index(3), 0);
+        let forms = PluralForms::parse("nplurals=2; plural=1 / n;").unwrap();
+        assert_eq!(forms.index(0), 0);
+        // Only the subexpression that divides by zero is 0, not the whole expression.
+        let forms = PluralForms::parse("nplurals=3; plural=n % 0 ? 1 : 2;").unwrap();
+        assert_eq!(forms.index(5), 2);
+        let forms = PluralForms::parse("nplurals=3; plural=(n / 0) + 2;").unwrap();
+        assert_eq!(forms.index(5), 2);
+    }
+
+    #[test]
+    fn headers_are_parsed() {
+        let header = "Content-Type: text/plain; charset=UTF-8\n\
+                      Plural-Forms: nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;\n";
+        let forms = PluralForms::from_header(header).unwrap();
+        assert_eq!(forms.nplurals(), 3);
+        assert_eq!(forms.index(2), 1);
+
+        assert_eq!(
+            PluralForms::from_header("Content-Type: text/plain; charset=UTF-8\n"),
+            Err(PluralFormsError::MissingHeader)
+        );
+        assert_eq!(
+            PluralForms::parse(" plural=n != 1; "),
+            Err(PluralFormsError::MissingNplurals)
+        );
+        assert_eq!(
+            PluralForms::parse("nplurals=0; plural=0;"),
+            Err(PluralFormsError::InvalidNplurals("0".to_owned()))
+        );
+        assert_eq!(
+            PluralForms::parse("nplurals=2;"),
+            Err(PluralFormsError::MissingPlural)
+        );
+        assert_eq!(
+            PluralForms::default(),
+            PluralForms::parse("nplurals=2; plural=n != 1;").unwrap()
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert_eq!(
+            Expression::parse("n +"),
+            Err(PluralFormsError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Expression::parse("n ? 1"),
+            Err(PluralFormsError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Expression::parse("(n"),
+            Err(PluralFormsError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Expression::parse("n = 1"),
+            Err(PluralFormsError::UnexpectedToken(2))
+        );
+        assert_eq!(
+            Expression::parse("n != 1 x"),
+            Err(PluralFormsError::UnexpectedToken(7))
+        );
+        assert_eq!(
+            Expression::parse("m"),
+            Err(PluralFormsError::UnexpectedToken(0))
+        );
+        assert_eq!(Expression::parse(""), Err(PluralFormsError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |open: &str, middle: &str, close: &str, count: usize| {
+            format!("{}{}{}", open.repeat(count), middle, close.repeat(count))
+        };
+
+        assert!(Expression::parse(&nested("(", "n", ")", 50)).is_ok());
+        assert!(Expression::parse(&nested("n + ", "n", "", 50)).is_ok());
+        for expression in &[
+            nested("(", "n", ")", 200_000),
+            nested("!", "n", "", 200_000),
+            nested("n ? 1 : ", "n", "", 200_000),
+            nested("n + ", "n", "", 200_000),
+            nested("(n + ", "n", ")", 200_000),
+        ] {
+            assert_eq!(
+                Expression::parse(expression),
+                Err(PluralFormsError::TooDeeplyNested)
+            );
+        }
+    }
+}
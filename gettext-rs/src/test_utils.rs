@@ -0,0 +1,45 @@
+//! Helpers shared by the unit tests.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A temporary directory that is removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory whose name starts with `name`. A counter keeps the names unique
+    /// among tests that run in parallel.
+    pub fn new(name: &str) -> TempDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "gettext-rs-{}-{}-{}",
+            name,
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Creates empty files at `paths`, along with their parent directories.
+pub fn create_empty_files<P: AsRef<Path>>(paths: &[P]) {
+    for path in paths {
+        let path = path.as_ref();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+}
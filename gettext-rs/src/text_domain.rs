@@ -4,14 +4,25 @@ use locale_config::{LanguageRange, Locale};
 
 use std::env;
 use std::error;
+use std::ffi::OsString;
 use std::fmt;
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::{bind_textdomain_codeset, bindtextdomain, setlocale, textdomain, LocaleCategory};
+#[cfg(feature = "pure-rust")]
+use embedded::{self, EmbeddedCatalog};
+#[cfg(feature = "pure-rust")]
+use ffi;
+use layout::{Layout, DEFAULT_LAYOUT};
+use locale_name::{
+    language_part, languages, locale_from_env, locale_variants, non_empty_var, xpg_locale_name,
+};
 
 /// Errors that might come up after running the builder.
+///
+/// More variants might be added in future versions, so matches need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TextDomainError {
     /// The locale is malformed.
     InvalidLocale(String),
@@ -23,6 +34,12 @@ pub enum TextDomainError {
     BindTextDomainCallFailed(std::io::Error),
     /// The call to `bind_textdomain_codeset()` failed.
     BindTextDomainCodesetCallFailed(std::io::Error),
+    /// The layout set with [`TextDomain::layout`] can't be used with libintl, which only finds
+    /// catalogs in `language/category/domain.mo` under the bound directory. Such layouts need the
+    /// `pure-rust` feature.
+    ///
+    /// [`TextDomain::layout`]: struct.TextDomain.html#method.layout
+    UnsupportedLayout(String),
 }
 
 impl fmt::Display for TextDomainError {
@@ -41,6 +58,11 @@ impl fmt::Display for TextDomainError {
             BindTextDomainCodesetCallFailed(inner) => {
                 write!(f, "The call to bind_textdomain_codeset() failed: {}", inner)
             }
+            UnsupportedLayout(layout) => write!(
+                f,
+                r#"Layout "{}" can't be used with libintl, it has to end with "{{language}}/{{category}}/{{domain}}.mo"."#,
+                layout
+            ),
         }
     }
 }
@@ -55,7 +77,207 @@ impl error::Error for TextDomainError {
             TextDomainCallFailed(inner) => Some(inner),
             BindTextDomainCallFailed(inner) => Some(inner),
             BindTextDomainCodesetCallFailed(inner) => Some(inner),
+            UnsupportedLayout(_) => None,
+        }
+    }
+}
+
+/// A translation found by [`TextDomain`]. See [`TextDomain::resolve`] and
+/// [`TextDomain::available_languages`].
+///
+/// [`TextDomain`]: struct.TextDomain.html
+/// [`TextDomain::resolve`]: struct.TextDomain.html#method.resolve
+/// [`TextDomain::available_languages`]: struct.TextDomain.html#method.available_languages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundCatalog {
+    /// The language of the translation, i.e. the name of the directory it's in (like `pt_BR`).
+    pub language: String,
+    /// The text domain.
+    pub domain: String,
+    /// The locale category of the translation, i.e. the name of the directory it's in within the
+    /// language directory (like `LC_MESSAGES`).
+    pub category: LocaleCategory,
+    /// The path of the MO file. For embedded catalogs, it starts with [`embedded::DIRNAME`].
+    ///
+    /// [`embedded::DIRNAME`]: embedded/constant.DIRNAME.html
+    pub path: PathBuf,
+    /// The directory that gets bound to the text domain with [`bindtextdomain`]. With a
+    /// [`layout`] that libintl can't use, it's the directory that contains the MO file.
+    ///
+    /// [`layout`]: struct.TextDomain.html#method.layout
+    /// [`bindtextdomain`]: fn.bindtextdomain.html
+    pub dirname: PathBuf,
+}
+
+/// What [`TextDomain::init`] set up. Applications can log it, or show it in an "About" dialog.
+///
+/// The [`Display`](fmt::Display) implementation lists everything on separate lines.
+///
+/// [`TextDomain::init`]: struct.TextDomain.html#method.init
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitOutcome {
+    /// The locale set with [`TextDomain::locale`], or `None` if the user's locale was used.
+    ///
+    /// [`TextDomain::locale`]: struct.TextDomain.html#method.locale
+    pub requested_locale: Option<String>,
+    /// The opaque string that describes the locale that was set, i.e. the result of
+    /// [`setlocale`]. It's `None` if `setlocale` failed, or in [`library_mode`], where it isn't
+    /// called.
+    ///
+    /// [`setlocale`]: fn.setlocale.html
+    /// [`library_mode`]: struct.TextDomain.html#method.library_mode
+    pub effective_locale: Option<Vec<u8>>,
+    /// Whether [`setlocale`] failed, e.g. because the locale isn't installed. The translation is
+    /// bound anyway, but messages might not be translated, or not converted to the right codeset.
+    ///
+    /// [`setlocale`]: fn.setlocale.html
+    pub setlocale_failed: bool,
+    /// The language of the translation that was picked, i.e. the name of the directory it was
+    /// found in (like `pt_BR` or `pt`). It's `None` if the locale is "C" or "POSIX", as nothing
+    /// is searched for then.
+    pub language: Option<String>,
+    /// The directory that was bound to the text domain with [`bindtextdomain`], or `None` if the
+    /// locale is "C" or "POSIX".
+    ///
+    /// [`bindtextdomain`]: fn.bindtextdomain.html
+    pub dirname: Option<PathBuf>,
+    /// The text domain.
+    pub domain: String,
+    /// The codeset that messages are converted to, set with [`bind_textdomain_codeset`]. It's
+    /// `None` if no translation was found, as the text domain isn't bound then.
+    ///
+    /// [`bind_textdomain_codeset`]: fn.bind_textdomain_codeset.html
+    pub codeset: Option<String>,
+}
+
+impl fmt::Display for InitOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "domain: {}", self.domain)?;
+        writeln!(
+            f,
+            "requested locale: {}",
+            self.requested_locale
+                .as_deref()
+                .unwrap_or("(from the environment)")
+        )?;
+        match self.effective_locale {
+            Some(ref locale) => writeln!(f, "locale: {}", String::from_utf8_lossy(locale))?,
+            None if self.setlocale_failed => writeln!(f, "locale: setlocale failed")?,
+            None => writeln!(f, "locale: unchanged")?,
+        }
+        match (&self.language, &self.dirname) {
+            (Some(language), Some(dirname)) => {
+                writeln!(f, "language: {}", language)?;
+                writeln!(f, "directory: {}", dirname.display())?;
+            }
+            _ => writeln!(f, "language: none")?,
         }
+        write!(
+            f,
+            "codeset: {}",
+            self.codeset.as_deref().unwrap_or("unchanged")
+        )
+    }
+}
+
+/// What [`TextDomain`] did to find a translation. See [`TextDomain::init_with_report`].
+///
+/// The [`Display`](fmt::Display) implementation lists everything on separate lines, which is
+/// handy for logging.
+///
+/// [`TextDomain`]: struct.TextDomain.html
+/// [`TextDomain::init_with_report`]: struct.TextDomain.html#method.init_with_report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchReport {
+    /// The languages that were tried, in order of preference. It's empty if the locale is "C" or
+    /// "POSIX", or if the search didn't start because of an error.
+    pub languages: Vec<String>,
+    /// The search paths, in the order in which they were searched.
+    pub paths: Vec<SearchedPath>,
+    /// The catalog that was picked, if any.
+    pub mo_path: Option<PathBuf>,
+    /// The reports for the domains added with [`TextDomain::secondary_domain`], along with their
+    /// names. They're only searched for if a translation was found for the main domain.
+    ///
+    /// [`TextDomain::secondary_domain`]: struct.TextDomain.html#method.secondary_domain
+    pub secondary_domains: Vec<(String, SearchReport)>,
+}
+
+/// A path that [`TextDomain`] searched for translations, as part of a [`SearchReport`].
+///
+/// [`TextDomain`]: struct.TextDomain.html
+/// [`SearchReport`]: struct.SearchReport.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchedPath {
+    /// The search path, e.g. one added with [`prepend`] or taken from `XDG_DATA_DIRS`. Embedded
+    /// catalogs are represented by [`embedded::DIRNAME`].
+    ///
+    /// [`prepend`]: struct.TextDomain.html#method.prepend
+    /// [`embedded::DIRNAME`]: embedded/constant.DIRNAME.html
+    pub path: PathBuf,
+    /// The directory whose subdirectories are named after languages, i.e. `path/locale`. With a
+    /// custom [`layout`], it's the directory in which the placeholders start, e.g. `path/i18n`
+    /// for `i18n/{domain}-{language}.mo`.
+    ///
+    /// [`layout`]: struct.TextDomain.html#method.layout
+    pub locale_dir: PathBuf,
+    /// Whether `locale_dir` exists. If it doesn't, no language directories were looked into.
+    pub locale_dir_exists: bool,
+    /// The languages that were looked for in `locale_dir`, in order, i.e. the names of its
+    /// subdirectories with the default layout. The last one is the language of the catalog if it
+    /// was picked from this path.
+    pub language_dirs: Vec<String>,
+}
+
+impl SearchedPath {
+    fn new(path: PathBuf, layout: &Layout) -> SearchedPath {
+        #[cfg(feature = "pure-rust")]
+        {
+            if embedded::is_dirname(&path) {
+                return SearchedPath {
+                    locale_dir: path.clone(),
+                    locale_dir_exists: true,
+                    path,
+                    language_dirs: vec![],
+                };
+            }
+        }
+
+        let locale_dir = layout.root_in(&path);
+        SearchedPath {
+            locale_dir_exists: locale_dir.is_dir(),
+            locale_dir,
+            path,
+            language_dirs: vec![],
+        }
+    }
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "languages: {}", self.languages.join(", "))?;
+        for searched in &self.paths {
+            if !searched.locale_dir_exists {
+                writeln!(f, "{}: does not exist", searched.locale_dir.display())?;
+            } else if searched.language_dirs.is_empty() {
+                writeln!(f, "{}: not searched", searched.locale_dir.display())?;
+            } else {
+                writeln!(
+                    f,
+                    "{}: tried {}",
+                    searched.locale_dir.display(),
+                    searched.language_dirs.join(", ")
+                )?;
+            }
+        }
+        match self.mo_path {
+            Some(ref mo_path) => write!(f, "picked {}", mo_path.display())?,
+            None => write!(f, "no translation found")?,
+        }
+        for (domainname, report) in &self.secondary_domains {
+            write!(f, "\n\nsecondary domain {}:\n{}", domainname, report)?;
+        }
+        Ok(())
     }
 }
 
@@ -63,7 +285,8 @@ impl error::Error for TextDomainError {
 ///
 /// It searches translations in the system data paths and optionally in the user-specified paths,
 /// and binds them to the given domain. `TextDomain` takes care of calling [`setlocale`],
-/// [`bindtextdomain`], [`bind_textdomain_codeset`], and [`textdomain`] for you.
+/// [`bindtextdomain`], [`bind_textdomain_codeset`], and [`textdomain`] for you. The text domains
+/// of plugins or libraries can be set up at the same time with [`secondary_domain`].
 ///
 /// # Defaults
 ///
@@ -72,8 +295,8 @@ impl error::Error for TextDomainError {
 /// UTF-8](./index.html#utf-8-is-required).
 /// - Current user's locale is selected by default. You can override this behaviour by calling
 /// [`locale`].
-/// - [`LocaleCategory::LcMessages`] is used when calling [`setlocale`]. Use [`locale_category`]
-/// to override.
+/// - [`LocaleCategory::LcMessages`] is used when calling [`setlocale`] and searching for catalogs.
+/// Use [`locale_category`] to override, and [`search_category`] to search for more categories.
 /// - System data paths are searched by default (see below for details). Use
 /// [`skip_system_data_paths`] to limit the search to user-provided paths.
 ///
@@ -82,15 +305,49 @@ impl error::Error for TextDomainError {
 /// A translation file for the text domain is searched in the following paths (in order):
 ///
 /// 1. Paths added using the [`prepend`] function.
+/// 1. Paths added using the [`exe_relative`] function, relative to the directory of the
+///    executable.
+/// 1. Catalogs added using the [`embedded`] function (only with the `pure-rust` feature). These
+///    are matched against the language the same way as the subdirectories described below.
+/// 1. The user's data directory from the `XDG_DATA_HOME` environment variable, except if the
+///    function [`skip_data_home`] or [`skip_system_data_paths`] was invoked. If `XDG_DATA_HOME`
+///    is not set, or is not an absolute path, the default of `$HOME/.local/share` is used.
 /// 1. Paths from the `XDG_DATA_DIRS` environment variable, except if the function
 /// [`skip_system_data_paths`] was invoked. If `XDG_DATA_DIRS` is not set, or is empty, the default
 /// of "/usr/local/share/:/usr/share/" is used.
 /// 1. Paths added using the [`push`] function.
 ///
-/// For each `path` in the search paths, the following subdirectories are scanned:
-/// `path/locale/lang*/LC_MESSAGES` (where `lang` is the language part of the selected locale).
-/// The first `path` containing a file matching `domainname.mo` is used for the call to
-/// [`bindtextdomain`].
+/// The paths from `XDG_DATA_HOME` and `XDG_DATA_DIRS` are the system data paths, which
+/// [`system_data_paths`] returns.
+///
+/// For each `path` in the search paths, the file `path/locale/lang/category/domainname.mo` is
+/// looked up, where `lang` is the selected language and `category` is the one set with
+/// [`locale_category`], like `LC_MESSAGES` or `LC_TIME`. [`LocaleCategory::LcAll`] has no
+/// catalogs of its own, so `LC_MESSAGES` is used for it. The first `path` containing such a file
+/// is used for the call to [`bindtextdomain`].
+///
+/// Other layouts, like `i18n/{domain}-{language}.mo`, can be set with [`layout`].
+///
+/// Catalogs for the categories added with [`search_category`] are looked up in that same
+/// `path/locale` directory, since libintl binds a directory to a whole text domain. They're
+/// optional: `init` succeeds as long as the catalog for the main category is found.
+///
+/// Like gettext itself, `TextDomain` honours the `LANGUAGE` environment variable: it's a list of
+/// languages in order of preference, like `pt_BR:pt:es`. Each of them is tried against all the
+/// search paths before moving on to the next one. Without `LANGUAGE`, the locale is taken from
+/// `LC_ALL`, the variable of the category (e.g. `LC_MESSAGES`), or `LANG`, or set with
+/// [`locale`]. The [`InitOutcome`] returned by [`init`] tells which language was picked.
+///
+/// Languages are locale names of the form `language[_TERRITORY][.codeset][@modifier]`. Like GNU
+/// gettext, `TextDomain` tries the parts of the name from the most specific to the least, keeping
+/// the modifier the longest. For `de_DE.UTF-8@euro`, the directories are tried in this order:
+///
+/// 1. `de_DE.UTF-8@euro`, `de_DE.utf8@euro` (the codeset normalized), `de_DE@euro`
+/// 1. `de.UTF-8@euro`, `de.utf8@euro`, `de@euro`
+/// 1. `de_DE.UTF-8`, `de_DE.utf8`, `de_DE`
+/// 1. `de.UTF-8`, `de.utf8`, `de`
+///
+/// Other directories never match: `sr` doesn't pick `sr@latin`, and `en_US` doesn't pick `en_GB`.
 ///
 /// # Examples
 ///
@@ -130,8 +387,8 @@ impl error::Error for TextDomainError {
 ///     .locale("fr_FR")
 ///     .init()
 /// {
-///     Ok(locale) => {
-///         format!("translation found, `setlocale` returned {:?}", locale)
+///     Ok(outcome) => {
+///         format!("translation found, `setlocale` returned {:?}", outcome.effective_locale)
 ///     }
 ///     Err(error) => {
 ///         format!("an error occurred: {}", error)
@@ -146,19 +403,37 @@ impl error::Error for TextDomainError {
 /// [`textdomain`]: fn.textdomain.html
 /// [`LocaleCategory::LcMessages`]: enum.LocaleCategory.html#variant.LcMessages
 /// [`locale`]: struct.TextDomain.html#method.locale
+/// [`LocaleCategory::LcAll`]: enum.LocaleCategory.html#variant.LcAll
 /// [`locale_category`]: struct.TextDomain.html#method.locale_category
+/// [`search_category`]: struct.TextDomain.html#method.search_category
+/// [`layout`]: struct.TextDomain.html#method.layout
 /// [`codeset`]: struct.TextDomain.html#method.codeset
 /// [`skip_system_data_paths`]: struct.TextDomain.html#method.skip_system_data_paths
+/// [`skip_data_home`]: struct.TextDomain.html#method.skip_data_home
+/// [`system_data_paths`]: struct.TextDomain.html#method.system_data_paths
 /// [`prepend`]: struct.TextDomain.html#method.prepend
+/// [`exe_relative`]: struct.TextDomain.html#method.exe_relative
 /// [`push`]: struct.TextDomain.html#method.push
+/// [`embedded`]: struct.TextDomain.html#method.embedded
+/// [`InitOutcome`]: struct.InitOutcome.html
+/// [`init`]: struct.TextDomain.html#method.init
+/// [`secondary_domain`]: struct.TextDomain.html#method.secondary_domain
 pub struct TextDomain {
     domainname: String,
     locale: Option<String>,
     locale_category: LocaleCategory,
+    search_categories: Vec<LocaleCategory>,
+    layout: Layout,
     codeset: String,
     pre_paths: Vec<PathBuf>,
+    exe_relative_paths: Vec<PathBuf>,
     post_paths: Vec<PathBuf>,
+    #[cfg(feature = "pure-rust")]
+    embedded: &'static [EmbeddedCatalog],
     skip_system_data_paths: bool,
+    skip_data_home: bool,
+    secondary_domains: Vec<(String, Vec<PathBuf>)>,
+    library_mode: bool,
 }
 
 impl TextDomain {
@@ -176,10 +451,18 @@ impl TextDomain {
             domainname: domainname.into(),
             locale: None,
             locale_category: LocaleCategory::LcMessages,
+            search_categories: vec![],
+            layout: Layout::new(DEFAULT_LAYOUT),
             codeset: "UTF-8".to_string(),
             pre_paths: vec![],
+            exe_relative_paths: vec![],
             post_paths: vec![],
+            #[cfg(feature = "pure-rust")]
+            embedded: &[],
             skip_system_data_paths: false,
+            skip_data_home: false,
+            secondary_domains: vec![],
+            library_mode: false,
         }
     }
 
@@ -200,6 +483,9 @@ impl TextDomain {
 
     /// Override the `locale_category`. Default is [`LocaleCategory::LcMessages`].
     ///
+    /// Translations are searched for in the directory of this category, or in `LC_MESSAGES` for
+    /// [`LocaleCategory::LcAll`].
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -210,11 +496,68 @@ impl TextDomain {
     /// ```
     ///
     /// [`LocaleCategory::LcMessages`]: enum.LocaleCategory.html#variant.LcMessages
+    /// [`LocaleCategory::LcAll`]: enum.LocaleCategory.html#variant.LcAll
     pub fn locale_category(mut self, locale_category: LocaleCategory) -> Self {
         self.locale_category = locale_category;
         self
     }
 
+    /// Also look for catalogs of `category`, so that [`dcgettext`] and similar functions find
+    /// translations for it. They're searched for in the directory where the catalog for the
+    /// [`locale_category`] was found, in the same languages, and it's not an error if there's
+    /// none.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::{LocaleCategory, TextDomain};
+    ///
+    /// let text_domain = TextDomain::new("my_textdomain")
+    ///                              .locale_category(LocaleCategory::LcAll)
+    ///                              .search_category(LocaleCategory::LcTime);
+    /// ```
+    ///
+    /// [`dcgettext`]: fn.dcgettext.html
+    /// [`locale_category`]: struct.TextDomain.html#method.locale_category
+    pub fn search_category(mut self, category: LocaleCategory) -> Self {
+        self.search_categories.push(category);
+        self
+    }
+
+    /// Set where catalogs are in each search path, as a path relative to it with the placeholders
+    /// `{language}`, `{category}`, and `{domain}`. The default is
+    /// `locale/{language}/{category}/{domain}.mo`, which is what GNU gettext uses.
+    ///
+    /// Languages are matched the same way with any layout: each variant of the language name
+    /// replaces `{language}` in turn, and [`available_languages`] lists the files that match the
+    /// layout.
+    ///
+    /// libintl looks for the catalogs on its own, in `language/category/domain.mo` under the
+    /// directory that [`bindtextdomain`] binds, so layouts ending that way are bound to the
+    /// directory before `{language}`, e.g. `share/my_app/locale` for
+    /// `share/{domain}/locale/{language}/{category}/{domain}.mo`. Other layouts are only supported
+    /// with the `pure-rust` feature, which is given the path of the catalog directly; with
+    /// libintl, [`init`] fails with [`TextDomainError::UnsupportedLayout`]. Embedded catalogs
+    /// always use the default layout.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// let text_domain = TextDomain::new("my_mod")
+    ///                              .layout("i18n/{domain}-{language}.mo");
+    /// ```
+    ///
+    /// [`available_languages`]: struct.TextDomain.html#method.available_languages
+    /// [`bindtextdomain`]: fn.bindtextdomain.html
+    /// [`init`]: struct.TextDomain.html#method.init
+    /// [`TextDomainError::UnsupportedLayout`]: enum.TextDomainError.html#variant.UnsupportedLayout
+    pub fn layout<S: Into<String>>(mut self, template: S) -> Self {
+        self.layout = Layout::new(&template.into());
+        self
+    }
+
     /// Define the `codeset` that will be used for calling [`bind_textdomain_codeset`]. The default
     /// is "UTF-8".
     ///
@@ -265,7 +608,63 @@ impl TextDomain {
         self
     }
 
-    /// Don't search for translations in the system data paths.
+    /// Search for translations in `path` relative to the directory of the running executable, as
+    /// returned by [`std::env::current_exe`]. Such paths are searched after the ones added with
+    /// [`prepend`], and before embedded catalogs and the system data paths.
+    ///
+    /// This lets programs that are moved around as a whole, like AppImages or unpacked tarballs,
+    /// find their catalogs without wrapper scripts. If the executable can't be located, the path is
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// With the program in `bin/`, look for catalogs in `share/locale/` next to it:
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// let text_domain = TextDomain::new("my_textdomain")
+    ///                              .exe_relative("../share");
+    /// ```
+    ///
+    /// [`prepend`]: struct.TextDomain.html#method.prepend
+    pub fn exe_relative<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.exe_relative_paths.push(path.into());
+        self
+    }
+
+    /// Search for translations in the catalogs embedded in the program, after the paths added with
+    /// [`prepend`] and [`exe_relative`] and before the system data paths. Replaces the catalogs set
+    /// by earlier calls.
+    ///
+    /// This is only available with the `pure-rust` feature. See the [`embedded`] module for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::embedded::EmbeddedCatalog;
+    /// use gettextrs::TextDomain;
+    ///
+    /// static CATALOGS: &[EmbeddedCatalog] = &[
+    ///     // Usually filled by `include_catalogs!()`.
+    /// ];
+    ///
+    /// let text_domain = TextDomain::new("my_textdomain")
+    ///                              .embedded(CATALOGS);
+    /// ```
+    ///
+    /// [`prepend`]: struct.TextDomain.html#method.prepend
+    /// [`exe_relative`]: struct.TextDomain.html#method.exe_relative
+    /// [`embedded`]: embedded/index.html
+    #[cfg(feature = "pure-rust")]
+    pub fn embedded(mut self, catalogs: &'static [EmbeddedCatalog]) -> Self {
+        self.embedded = catalogs;
+        self
+    }
+
+    /// Don't search for translations in the system data paths, including the user's data
+    /// directory.
     ///
     /// # Examples
     ///
@@ -281,14 +680,147 @@ impl TextDomain {
         self
     }
 
+    /// Don't search for translations in the user's data directory, `XDG_DATA_HOME`, but still
+    /// search the other system data paths.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// let text_domain = TextDomain::new("my_textdomain")
+    ///                              .skip_data_home();
+    /// ```
+    pub fn skip_data_home(mut self) -> Self {
+        self.skip_data_home = true;
+        self
+    }
+
+    /// Returns the system data paths that will be searched, in order: the user's data directory
+    /// from `XDG_DATA_HOME`, then the directories from `XDG_DATA_DIRS`. See the [search
+    /// order](struct.TextDomain.html#text-domain-path-binding) for the defaults.
+    ///
+    /// The list is empty if [`skip_system_data_paths`] was called, and doesn't include the user's
+    /// data directory if [`skip_data_home`] was.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// for path in TextDomain::new("my_textdomain").system_data_paths() {
+    ///     println!("{}", path.display());
+    /// }
+    /// ```
+    ///
+    /// [`skip_system_data_paths`]: struct.TextDomain.html#method.skip_system_data_paths
+    /// [`skip_data_home`]: struct.TextDomain.html#method.skip_data_home
+    pub fn system_data_paths(&self) -> Vec<PathBuf> {
+        if self.skip_system_data_paths {
+            return vec![];
+        }
+
+        let data_home = if self.skip_data_home {
+            None
+        } else {
+            data_home(non_empty_var("XDG_DATA_HOME"), env::var_os("HOME"))
+        };
+        data_home
+            .into_iter()
+            .chain(data_dirs(non_empty_var("XDG_DATA_DIRS")))
+            .collect()
+    }
+
+    /// Only bind the text domain to the directory with its translation and set its codeset; don't
+    /// call [`setlocale`] or [`textdomain`]. The [`InitOutcome`] returned by [`init`] then has no
+    /// `effective_locale`.
+    ///
+    /// This is meant for libraries: the locale and the default text domain belong to the
+    /// application, so libraries shouldn't change them. Look messages up with a [`Domain`] or with
+    /// [`dgettext`] and similar functions instead of [`gettext`].
+    ///
+    /// The translation is searched for in the language of the user's locale, or in the one set
+    /// with [`locale`], as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// TextDomain::new("my_library").library_mode().init()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`setlocale`]: fn.setlocale.html
+    /// [`textdomain`]: fn.textdomain.html
+    /// [`init`]: struct.TextDomain.html#method.init
+    /// [`InitOutcome`]: struct.InitOutcome.html
+    /// [`Domain`]: struct.Domain.html
+    /// [`dgettext`]: fn.dgettext.html
+    /// [`gettext`]: fn.gettext.html
+    /// [`locale`]: struct.TextDomain.html#method.locale
+    pub fn library_mode(mut self) -> Self {
+        self.library_mode = true;
+        self
+    }
+
+    /// Set up another text domain along with the main one, e.g. the domain of a plugin or a
+    /// library. Its translation is searched for in `paths` first, and then in the search paths of
+    /// the main text domain, in the same languages.
+    ///
+    /// [`bindtextdomain`] and [`bind_textdomain_codeset`] are called for secondary domains, but
+    /// [`textdomain`] isn't, so their messages have to be looked up with [`dgettext`] and similar
+    /// functions. If no translation is found for a secondary domain, it's left alone and `init`
+    /// still succeeds; [`init_with_report`] tells which ones were found. Calling this again with
+    /// the same `domainname` adds more paths.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    /// use std::path::PathBuf;
+    ///
+    /// let text_domain = TextDomain::new("my_textdomain")
+    ///                              .secondary_domain("my_plugin", vec!["plugins/my_plugin"])
+    ///                              .secondary_domain("my_library", Vec::<PathBuf>::new());
+    /// ```
+    ///
+    /// [`bindtextdomain`]: fn.bindtextdomain.html
+    /// [`bind_textdomain_codeset`]: fn.bind_textdomain_codeset.html
+    /// [`textdomain`]: fn.textdomain.html
+    /// [`dgettext`]: fn.dgettext.html
+    /// [`init_with_report`]: struct.TextDomain.html#method.init_with_report
+    pub fn secondary_domain<S, I, P>(mut self, domainname: S, paths: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        let domainname = domainname.into();
+        let paths = paths.into_iter().map(Into::into);
+        match self
+            .secondary_domains
+            .iter_mut()
+            .find(|(name, _)| *name == domainname)
+        {
+            Some((_, existing)) => existing.extend(paths),
+            None => self.secondary_domains.push((domainname, paths.collect())),
+        }
+        self
+    }
+
     /// Search for translations in the search paths, initialize the locale, set up the text domain
     /// and ask gettext to convert messages to UTF-8.
     ///
-    /// Returns an `Option` with the opaque string that describes the locale set (i.e. the result
-    /// of [`setlocale`]) if:
+    /// If a translation of the text domain in the requested language was found, returns an
+    /// [`InitOutcome`] that tells which language and directory were picked, and what [`setlocale`]
+    /// returned. Failing to set the locale isn't an error, since the translation is bound anyway;
+    /// check [`InitOutcome::setlocale_failed`] for that.
     ///
-    /// - a translation of the text domain in the requested language was found; and
-    /// - the locale is valid.
+    /// If the locale is "C" or "POSIX", whether it's set explicitly or taken from the environment,
+    /// nothing is searched for or bound, as there are no translations; only the locale is set.
     ///
     /// # Examples
     ///
@@ -296,114 +828,517 @@ impl TextDomain {
     /// use gettextrs::TextDomain;
     ///
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// TextDomain::new("my_textdomain").init()?;
+    /// let outcome = TextDomain::new("my_textdomain").init()?;
+    /// if let Some(language) = outcome.language {
+    ///     println!("Using the {} translation", language);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
     ///
-    /// [`TextDomainError`]: enum.TextDomainError.html
+    /// [`InitOutcome`]: struct.InitOutcome.html
+    /// [`InitOutcome::setlocale_failed`]: struct.InitOutcome.html#structfield.setlocale_failed
     /// [`setlocale`]: fn.setlocale.html
-    pub fn init(mut self) -> Result<Option<Vec<u8>>, TextDomainError> {
-        let (req_locale, norm_locale) = match self.locale.take() {
-            Some(req_locale) => {
+    pub fn init(self) -> Result<InitOutcome, TextDomainError> {
+        self.init_with_report().0
+    }
+
+    /// Same as [`init`], but also returns a [`SearchReport`] that tells which paths
+    /// and languages were tried, and which catalog was picked. The report is returned whether a
+    /// translation was found or not, which helps finding out why [`init`] failed with
+    /// [`TextDomainError::TranslationNotFound`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// let (result, report) = TextDomain::new("my_textdomain").init_with_report();
+    /// if let Err(error) = result {
+    ///     eprintln!("{}\n{}", error, report);
+    /// }
+    /// ```
+    ///
+    /// [`init`]: struct.TextDomain.html#method.init
+    /// [`SearchReport`]: struct.SearchReport.html
+    /// [`TextDomainError::TranslationNotFound`]: enum.TextDomainError.html#variant.TranslationNotFound
+    pub fn init_with_report(self) -> (Result<InitOutcome, TextDomainError>, SearchReport) {
+        let mut report = SearchReport::default();
+        let result = self.init_reporting(&mut report);
+        (result, report)
+    }
+
+    /// Searches for the translation that [`init`] would use, without changing the locale or
+    /// setting up any text domain.
+    ///
+    /// Returns `None` if the locale is "C" or "POSIX", explicitly or from the environment, as no
+    /// translation is used then. Fails like [`init`] if the locale is malformed or no translation
+    /// was found.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// if let Some(catalog) = TextDomain::new("my_textdomain").resolve()? {
+    ///     println!("would use {}", catalog.path.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`init`]: struct.TextDomain.html#method.init
+    pub fn resolve(&self) -> Result<Option<FoundCatalog>, TextDomainError> {
+        let found = self.resolve_reporting(&mut SearchReport::default())?;
+        Ok(found.map(|mut found| found.swap_remove(0)))
+    }
+
+    /// Lists the translations of the text domain in the search paths, whatever the user's locale
+    /// is. This is handy for letting users pick a language, e.g. in a settings dialog. Nothing is
+    /// changed: the locale and the text domains stay as they are.
+    ///
+    /// The catalogs are listed in the order of the search paths, and by language name within each
+    /// path. If a language is found in several paths, only the first catalog is listed, since
+    /// that's the one [`init`] would pick.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gettextrs::TextDomain;
+    ///
+    /// for catalog in TextDomain::new("my_textdomain").available_languages() {
+    ///     println!("{}", catalog.language);
+    /// }
+    /// ```
+    ///
+    /// [`init`]: struct.TextDomain.html#method.init
+    pub fn available_languages(&self) -> Vec<FoundCatalog> {
+        let mut found: Vec<FoundCatalog> = vec![];
+        let category = self.main_category();
+        for searched in self.search_paths() {
+            let mut catalogs = self.catalogs_in(&searched.locale_dir, category);
+            catalogs.sort();
+            for (language, path) in catalogs {
+                if found.iter().any(|catalog| catalog.language == language) {
+                    continue;
+                }
+                found.push(FoundCatalog {
+                    language,
+                    domain: self.domainname.clone(),
+                    category,
+                    dirname: self.dirname(&searched.locale_dir, &self.domainname, &path),
+                    path,
+                });
+            }
+        }
+        found
+    }
+
+    fn init_reporting(mut self, report: &mut SearchReport) -> Result<InitOutcome, TextDomainError> {
+        #[cfg(not(feature = "pure-rust"))]
+        {
+            if self.layout.bind_dir(Path::new(""), "").is_none() {
+                let layout = self.layout.template().to_owned();
+                return Err(TextDomainError::UnsupportedLayout(layout));
+            }
+        }
+
+        let found = self.resolve_reporting(report)?;
+        let requested_locale = self.locale.take();
+        let effective_locale = if self.library_mode {
+            None
+        } else {
+            // `setlocale` accepts an empty string for current locale
+            setlocale(
+                self.locale_category,
+                requested_locale.clone().unwrap_or_default(),
+            )
+        };
+        let mut outcome = InitOutcome {
+            setlocale_failed: !self.library_mode && effective_locale.is_none(),
+            requested_locale,
+            effective_locale,
+            language: None,
+            dirname: None,
+            domain: self.domainname.clone(),
+            codeset: None,
+        };
+        let found = match found {
+            Some(found) => found,
+            None => return Ok(outcome),
+        };
+
+        let secondary_domains = self
+            .secondary_domains
+            .iter()
+            .map(|(domainname, paths)| {
+                let mut secondary_report = SearchReport {
+                    languages: report.languages.clone(),
+                    ..SearchReport::default()
+                };
+                let found = self.search(domainname, paths, &mut secondary_report);
+                report
+                    .secondary_domains
+                    .push((domainname.clone(), secondary_report));
+                found
+            })
+            .collect::<Vec<_>>();
+
+        #[cfg(feature = "pure-rust")]
+        {
+            if embedded::is_dirname(&found[0].dirname)
+                || secondary_domains
+                    .iter()
+                    .flatten()
+                    .any(|found| embedded::is_dirname(&found[0].dirname))
+            {
+                embedded::mount(self.embedded);
+            }
+        }
+
+        for found in secondary_domains.into_iter().flatten() {
+            bind(found, &self.codeset)?;
+        }
+        outcome.language = Some(found[0].language.clone());
+        outcome.dirname = Some(found[0].dirname.clone());
+        bind(found, &self.codeset)?;
+        outcome.codeset = Some(self.codeset.clone());
+        if !self.library_mode {
+            textdomain(self.domainname).map_err(TextDomainError::TextDomainCallFailed)?;
+        }
+        Ok(outcome)
+    }
+
+    fn resolve_reporting(
+        &self,
+        report: &mut SearchReport,
+    ) -> Result<Option<Vec<FoundCatalog>>, TextDomainError> {
+        let locale_name = match self.locale {
+            Some(ref req_locale) => {
                 if req_locale == "C" || req_locale == "POSIX" {
-                    return Ok(Some(req_locale.as_bytes().to_owned()));
+                    return Ok(None);
                 }
-                match LanguageRange::new(&req_locale) {
-                    Ok(lang_range) => (req_locale.clone(), lang_range.into()),
-                    Err(_) => {
-                        // try again as unix language tag
-                        match LanguageRange::from_unix(&req_locale) {
-                            Ok(lang_range) => (req_locale.clone(), lang_range.into()),
-                            Err(_) => {
-                                return Err(TextDomainError::InvalidLocale(req_locale.clone()));
-                            }
-                        }
-                    }
+                // accept both language tags and unix locale names
+                if LanguageRange::new(req_locale)
+                    .or_else(|_| LanguageRange::from_unix(req_locale))
+                    .is_err()
+                {
+                    return Err(TextDomainError::InvalidLocale(req_locale.clone()));
                 }
+                xpg_locale_name(req_locale)
             }
+            None => locale_from_env(category_name(self.main_category()))
+                .unwrap_or_else(system_locale_name),
+        };
+        // Like an explicit one, a "C" locale from the environment has no translations
+        if locale_name == "C" || locale_name == "POSIX" {
+            return Ok(None);
+        }
+
+        report.languages = languages(&locale_name, non_empty_var("LANGUAGE"));
+        match self.search(&self.domainname, &[], report) {
+            Some(found) => Ok(Some(found)),
             None => {
-                // `setlocale` accepts an empty string for current locale
-                ("".to_owned(), Locale::current())
+                let language = report
+                    .languages
+                    .first()
+                    .map_or(locale_name.as_str(), |language| language_part(language));
+                Err(TextDomainError::TranslationNotFound(language.to_owned()))
             }
-        };
+        }
+    }
 
-        let lang = norm_locale.as_ref().splitn(2, "-").collect::<Vec<&str>>()[0].to_owned();
+    /// Searches `extra_paths` and then the search paths for a translation of `domainname` into
+    /// one of `report.languages`, and fills the rest of the report. The catalog for the main
+    /// category comes first, followed by the ones for the other categories.
+    fn search(
+        &self,
+        domainname: &str,
+        extra_paths: &[PathBuf],
+        report: &mut SearchReport,
+    ) -> Option<Vec<FoundCatalog>> {
+        report.paths = extra_paths
+            .iter()
+            .cloned()
+            .map(|path| SearchedPath::new(path, &self.layout))
+            .chain(self.search_paths())
+            .collect();
 
-        let domainname = self.domainname;
-        let locale_category = self.locale_category;
-        let codeset = self.codeset;
+        // Try the languages in order of preference, and search all paths for each of them
+        let category = self.main_category();
+        let SearchReport {
+            ref languages,
+            ref mut paths,
+            ..
+        } = *report;
+        let found = languages.iter().find_map(|language| {
+            paths.iter_mut().find_map(|searched| {
+                let locale_dir = searched.locale_dir.clone();
+                let mo_path = find_mo_file(searched, language, |variant| {
+                    self.lookup(domainname, &locale_dir, variant, category)
+                })?;
+                let found = FoundCatalog {
+                    language: searched.language_dirs.last()?.clone(),
+                    domain: domainname.to_owned(),
+                    category,
+                    dirname: self.dirname(&locale_dir, domainname, &mo_path),
+                    path: mo_path,
+                };
+                Some(self.with_other_categories(found, &locale_dir, languages))
+            })
+        })?;
+        report.mo_path = Some(found[0].path.clone());
+        Some(found)
+    }
 
-        let mo_rel_path = PathBuf::from("LC_MESSAGES").join(&format!("{}.mo", &domainname));
+    /// Returns the category whose catalogs are searched for first, and have to be found.
+    fn main_category(&self) -> LocaleCategory {
+        catalog_category(self.locale_category)
+    }
 
-        // Get paths from system data dirs if requested so
-        let sys_data_paths_str = if !self.skip_system_data_paths {
-            get_system_data_paths()
+    /// Returns `found` along with the catalogs of the same domain for the categories added with
+    /// `search_category`, from `locale_dir`, where `found` is. Each category is searched for in
+    /// all the `languages`, like libintl does once the domain is bound to that directory.
+    fn with_other_categories(
+        &self,
+        found: FoundCatalog,
+        locale_dir: &Path,
+        languages: &[String],
+    ) -> Vec<FoundCatalog> {
+        let mut categories: Vec<LocaleCategory> = vec![found.category];
+        let mut catalogs = vec![];
+        for category in self.search_categories.iter().cloned().map(catalog_category) {
+            if categories.contains(&category) {
+                continue;
+            }
+            categories.push(category);
+
+            let other = languages
+                .iter()
+                .flat_map(|language| locale_variants(language))
+                .find_map(|language| {
+                    let path = self.lookup(&found.domain, locale_dir, &language, category)?;
+                    Some(FoundCatalog {
+                        language,
+                        domain: found.domain.clone(),
+                        category,
+                        dirname: self.dirname(locale_dir, &found.domain, &path),
+                        path,
+                    })
+                });
+            catalogs.extend(other);
+        }
+        catalogs.insert(0, found);
+        catalogs
+    }
+
+    /// Returns the paths to search, in order.
+    fn search_paths(&self) -> Vec<SearchedPath> {
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_owned));
+        let exe_relative_iter = exe_dir.iter().flat_map(|exe_dir| {
+            self.exe_relative_paths
+                .iter()
+                .map(move |path| exe_dir.join(path))
+        });
+
+        // Embedded catalogs are searched as if they were in a directory of their own
+        #[cfg(feature = "pure-rust")]
+        let embedded_path = if self.embedded.is_empty() {
+            None
         } else {
-            "".to_owned()
+            Some(PathBuf::from(embedded::DIRNAME))
         };
-        let sys_data_dirs_iter = env::split_paths(&sys_data_paths_str);
+        #[cfg(not(feature = "pure-rust"))]
+        let embedded_path = None;
 
-        // Chain search paths and search for the translation mo file
+        // Chain search paths
         self.pre_paths
-            .into_iter()
-            .chain(sys_data_dirs_iter)
-            .chain(self.post_paths.into_iter())
-            .find(|path| {
-                let locale_path = path.join("locale");
-                if !locale_path.is_dir() {
-                    return false;
-                }
+            .iter()
+            .cloned()
+            .chain(exe_relative_iter)
+            .chain(embedded_path)
+            .chain(self.system_data_paths())
+            .chain(self.post_paths.iter().cloned())
+            .map(|path| SearchedPath::new(path, &self.layout))
+            .collect()
+    }
 
-                // path contains a `locale` directory
-                // search for sub directories matching `lang*`
-                // and see if we can find a translation file for the `textdomain`
-                // under `path/locale/lang*/LC_MESSAGES/`
-                if let Ok(entry_iter) = fs::read_dir(&locale_path) {
-                    return entry_iter
-                        .filter_map(|entry_res| entry_res.ok())
-                        .filter(|entry| {
-                            matches!(
-                                entry.file_type().map(|ft| ft.is_dir() || ft.is_symlink()),
-                                Ok(true)
-                            )
-                        })
-                        .any(|entry| {
-                            if let Some(entry_name) = entry.file_name().to_str() {
-                                return entry_name.starts_with(&lang)
-                                    && locale_path.join(entry_name).join(&mo_rel_path).exists();
-                            }
-
-                            false
-                        });
-                }
+    /// Returns the path of the catalog of `domainname` for `category` in the subdirectory
+    /// `language` of `locale_dir`, if there's one.
+    fn lookup(
+        &self,
+        domainname: &str,
+        locale_dir: &Path,
+        language: &str,
+        category: LocaleCategory,
+    ) -> Option<PathBuf> {
+        #[cfg(feature = "pure-rust")]
+        {
+            if embedded::is_dirname(locale_dir) {
+                let category = category_name(category);
+                return embedded::find(self.embedded, language, category, domainname);
+            }
+        }
 
-                false
-            })
-            .map_or(Err(TextDomainError::TranslationNotFound(lang)), |path| {
-                let result = setlocale(locale_category, req_locale);
-                bindtextdomain(domainname.clone(), path.join("locale"))
-                    .map_err(TextDomainError::BindTextDomainCallFailed)?;
-                bind_textdomain_codeset(domainname.clone(), codeset)
-                    .map_err(TextDomainError::BindTextDomainCodesetCallFailed)?;
-                textdomain(domainname).map_err(TextDomainError::TextDomainCallFailed)?;
-                Ok(result)
-            })
+        Some(
+            self.layout
+                .catalog_path(locale_dir, language, category_name(category), domainname),
+        )
+        .filter(|mo_path| mo_path.exists())
+    }
+
+    /// Returns the directory to bind `domainname` to, for the catalog at `mo_path` in
+    /// `locale_dir`.
+    fn dirname(&self, locale_dir: &Path, domainname: &str, mo_path: &Path) -> PathBuf {
+        #[cfg(feature = "pure-rust")]
+        {
+            if embedded::is_dirname(locale_dir) {
+                return locale_dir.to_owned();
+            }
+        }
+
+        self.layout
+            .bind_dir(locale_dir, domainname)
+            .or_else(|| mo_path.parent().map(Path::to_owned))
+            .unwrap_or_else(|| locale_dir.to_owned())
+    }
+
+    /// Returns the catalogs of the text domain for `category` in `locale_dir`, with their
+    /// languages.
+    fn catalogs_in(&self, locale_dir: &Path, category: LocaleCategory) -> Vec<(String, PathBuf)> {
+        #[cfg(feature = "pure-rust")]
+        {
+            if embedded::is_dirname(locale_dir) {
+                return self
+                    .embedded
+                    .iter()
+                    .filter_map(|catalog| {
+                        let language = catalog.language();
+                        let path = self.lookup(&self.domainname, locale_dir, language, category)?;
+                        Some((language.to_owned(), path))
+                    })
+                    .collect();
+            }
+        }
+
+        self.layout
+            .find_catalogs(locale_dir, category_name(category), &self.domainname)
     }
 }
 
-fn get_system_data_paths() -> String {
-    static DEFAULT: &str = "/usr/local/share/:/usr/share/";
+/// Searches the directory described by `searched` for a translation into `language`, and records
+/// the language subdirectories it looked into. `lookup` is given the name of a subdirectory, and
+/// returns the path of the catalog in it if there's one.
+///
+/// The variants of the language name are tried in the same order as GNU gettext does, e.g.
+/// `de_DE.UTF-8@euro`, `de_DE.utf8@euro`, `de_DE@euro`, `de.UTF-8@euro`, …, `de_DE`, `de.UTF-8`,
+/// `de.utf8`, `de`.
+fn find_mo_file<F>(searched: &mut SearchedPath, language: &str, lookup: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<PathBuf>,
+{
+    if !searched.locale_dir_exists {
+        return None;
+    }
 
-    if let Ok(dirs) = env::var("XDG_DATA_DIRS") {
-        if dirs.is_empty() {
-            DEFAULT.to_owned()
-        } else {
-            dirs
+    for variant in locale_variants(language) {
+        let mo_path = lookup(&variant);
+        searched.language_dirs.push(variant);
+        if mo_path.is_some() {
+            return mo_path;
         }
-    } else {
-        DEFAULT.to_owned()
     }
+    None
+}
+
+/// Returns the name of the user's locale as reported by the OS. It's used if none of the locale
+/// environment variables are set, which is usual on Windows.
+fn system_locale_name() -> String {
+    Locale::current()
+        .tags_for("messages")
+        .next()
+        .map(|tag| xpg_locale_name(tag.as_ref()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "C".to_owned())
+}
+
+/// Returns the category whose catalogs are used when the locale is set for `category`. `LC_ALL`
+/// has no catalogs of its own, so messages are looked up in `LC_MESSAGES` ones.
+fn catalog_category(category: LocaleCategory) -> LocaleCategory {
+    match category {
+        LocaleCategory::LcAll => LocaleCategory::LcMessages,
+        category => category,
+    }
+}
+
+/// Returns the name of the directory that holds catalogs for `category`, which is also the name of
+/// its environment variable.
+fn category_name(category: LocaleCategory) -> &'static str {
+    match category {
+        LocaleCategory::LcCType => "LC_CTYPE",
+        LocaleCategory::LcNumeric => "LC_NUMERIC",
+        LocaleCategory::LcTime => "LC_TIME",
+        LocaleCategory::LcCollate => "LC_COLLATE",
+        LocaleCategory::LcMonetary => "LC_MONETARY",
+        LocaleCategory::LcMessages => "LC_MESSAGES",
+        LocaleCategory::LcAll => "LC_ALL",
+        LocaleCategory::LcPaper => "LC_PAPER",
+        LocaleCategory::LcName => "LC_NAME",
+        LocaleCategory::LcAddress => "LC_ADDRESS",
+        LocaleCategory::LcTelephone => "LC_TELEPHONE",
+        LocaleCategory::LcMeasurement => "LC_MEASUREMENT",
+        LocaleCategory::LcIdentification => "LC_IDENTIFICATION",
+    }
+}
+
+/// Binds the text domain of the catalogs to the directory they were found in, and sets its
+/// codeset. All the catalogs are for the same domain and in the same directory.
+fn bind(found: Vec<FoundCatalog>, codeset: &str) -> Result<(), TextDomainError> {
+    let domain = found[0].domain.clone();
+    bindtextdomain(domain.clone(), found[0].dirname.clone())
+        .map_err(TextDomainError::BindTextDomainCallFailed)?;
+    for catalog in &found {
+        bind_catalog(&domain, catalog.category, &catalog.path);
+    }
+    bind_textdomain_codeset(domain, codeset)
+        .map_err(TextDomainError::BindTextDomainCodesetCallFailed)?;
+    Ok(())
+}
+
+/// Tells the pure-Rust backend which catalog `TextDomain` found, so that it doesn't have to
+/// search for it again.
+#[cfg(feature = "pure-rust")]
+fn bind_catalog(domainname: &str, category: LocaleCategory, mo_path: &Path) {
+    ffi::bind_catalog(domainname.as_bytes(), category as i32, mo_path);
+}
+
+/// libintl always searches for catalogs on its own, using the directory passed to
+/// `bindtextdomain`.
+#[cfg(not(feature = "pure-rust"))]
+fn bind_catalog(_domainname: &str, _category: LocaleCategory, _mo_path: &Path) {}
+
+/// Returns the user's data directory, given the values of `XDG_DATA_HOME` and `HOME`. As the XDG
+/// base directory specification requires, relative paths in `XDG_DATA_HOME` are ignored.
+fn data_home(xdg_data_home: Option<String>, home: Option<OsString>) -> Option<PathBuf> {
+    xdg_data_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            home.filter(|home| !home.is_empty())
+                .map(|home| Path::new(&home).join(".local/share"))
+        })
+}
+
+/// Returns the system's data directories, given the value of `XDG_DATA_DIRS`.
+fn data_dirs(xdg_data_dirs: Option<String>) -> Vec<PathBuf> {
+    static DEFAULT: &str = "/usr/local/share/:/usr/share/";
+
+    env::split_paths(xdg_data_dirs.as_deref().unwrap_or(DEFAULT))
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
 }
 
 impl fmt::Debug for TextDomain {
@@ -422,20 +1357,46 @@ impl fmt::Debug for TextDomain {
                 },
             )
             .field("locale_category", &self.locale_category)
+            .field("search_categories", &self.search_categories)
+            .field("layout", &self.layout.template())
             .field("codeset", &self.codeset)
-            .field("pre_paths", &self.pre_paths);
+            .field("pre_paths", &self.pre_paths)
+            .field("exe_relative_paths", &self.exe_relative_paths);
+
+        #[cfg(feature = "pure-rust")]
+        debug_struct.field(
+            "embedded",
+            &self
+                .embedded
+                .iter()
+                .map(|catalog| catalog.path)
+                .collect::<Vec<_>>(),
+        );
 
         if !self.skip_system_data_paths {
-            debug_struct.field("using system data paths", &get_system_data_paths());
+            debug_struct.field("using system data paths", &self.system_data_paths());
         }
 
-        debug_struct.field("post_paths", &self.post_paths).finish()
+        debug_struct
+            .field("post_paths", &self.post_paths)
+            .field("secondary_domains", &self.secondary_domains)
+            .field("library_mode", &self.library_mode)
+            .finish()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{LocaleCategory, TextDomain, TextDomainError};
+    use super::{
+        data_dirs, data_home, find_mo_file, LocaleCategory, SearchReport, SearchedPath, TextDomain,
+        TextDomainError,
+    };
+    use getters;
+    use layout::{Layout, DEFAULT_LAYOUT};
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use test_utils::{create_empty_files, TempDir};
 
     #[test]
     fn errors() {
@@ -448,6 +1409,12 @@ mod tests {
             Some(TextDomainError::TranslationNotFound(message)) => assert_eq!(message, "en"),
             _ => panic!(),
         };
+
+        #[cfg(not(feature = "pure-rust"))]
+        match TextDomain::new("0_0").layout("{domain}.mo").init().err() {
+            Some(TextDomainError::UnsupportedLayout(layout)) => assert_eq!(layout, "{domain}.mo"),
+            _ => panic!(),
+        };
     }
 
     #[test]
@@ -456,25 +1423,61 @@ mod tests {
         assert_eq!("test".to_owned(), text_domain.domainname);
         assert!(text_domain.locale.is_none());
         assert_eq!(LocaleCategory::LcMessages, text_domain.locale_category);
+        assert!(text_domain.search_categories.is_empty());
         assert_eq!(text_domain.codeset, "UTF-8");
         assert!(text_domain.pre_paths.is_empty());
+        assert!(text_domain.exe_relative_paths.is_empty());
         assert!(text_domain.post_paths.is_empty());
         assert!(!text_domain.skip_system_data_paths);
+        assert!(!text_domain.skip_data_home);
+        assert!(text_domain.secondary_domains.is_empty());
+        assert!(!text_domain.library_mode);
 
         let text_domain = text_domain.locale_category(LocaleCategory::LcAll);
         assert_eq!(LocaleCategory::LcAll, text_domain.locale_category);
 
+        let text_domain = text_domain.search_category(LocaleCategory::LcTime);
+        assert_eq!(vec![LocaleCategory::LcTime], text_domain.search_categories);
+
         let text_domain = text_domain.codeset("ISO-8859-15");
         assert_eq!("ISO-8859-15", text_domain.codeset);
 
         let text_domain = text_domain.prepend("pre");
         assert!(!text_domain.pre_paths.is_empty());
 
+        let text_domain = text_domain.exe_relative("../share");
+        assert_eq!(
+            text_domain.exe_relative_paths,
+            vec![PathBuf::from("../share")]
+        );
+
         let text_domain = text_domain.push("post");
         assert!(!text_domain.post_paths.is_empty());
 
+        let text_domain = text_domain.skip_data_home();
+        assert!(text_domain.skip_data_home);
+
         let text_domain = text_domain.skip_system_data_paths();
         assert!(text_domain.skip_system_data_paths);
+        assert!(text_domain.system_data_paths().is_empty());
+
+        let text_domain = text_domain.library_mode();
+        assert!(text_domain.library_mode);
+
+        let text_domain = text_domain
+            .secondary_domain("plugin", vec!["a"])
+            .secondary_domain("library", Vec::<PathBuf>::new())
+            .secondary_domain("plugin", vec!["b"]);
+        assert_eq!(
+            text_domain.secondary_domains,
+            vec![
+                (
+                    "plugin".to_owned(),
+                    vec![PathBuf::from("a"), PathBuf::from("b")]
+                ),
+                ("library".to_owned(), vec![]),
+            ]
+        );
 
         let text_domain = TextDomain::new("test").locale("en_US");
         assert_eq!(Some("en_US".to_owned()), text_domain.locale);
@@ -485,4 +1488,445 @@ mod tests {
             _ => panic!(),
         };
     }
+
+    #[cfg(feature = "pure-rust")]
+    #[test]
+    fn embedded_catalogs_are_searched() {
+        use embedded::{EmbeddedCatalog, DIRNAME};
+        use ffi;
+        use getters::domain_directory;
+        use std::path::PathBuf;
+
+        let data = ffi::mo_file(&[("Hello, world!", "Bonjour, le monde !")], false, true);
+        let catalogs: &'static [EmbeddedCatalog] = Box::leak(Box::new([EmbeddedCatalog {
+            path: "fr_FR/LC_MESSAGES/embedded_text_domain.mo",
+            data: Box::leak(data.into_boxed_slice()),
+        }]));
+
+        match TextDomain::new("embedded_text_domain")
+            .locale("de_DE")
+            .skip_system_data_paths()
+            .embedded(catalogs)
+            .init()
+            .err()
+        {
+            Some(TextDomainError::TranslationNotFound(message)) => assert_eq!(message, "de"),
+            _ => panic!(),
+        };
+
+        let available = TextDomain::new("embedded_text_domain")
+            .skip_system_data_paths()
+            .embedded(catalogs)
+            .available_languages();
+        assert_eq!(available.len(), 1);
+        assert_eq!(available[0].language, "fr_FR");
+        assert_eq!(available[0].dirname, PathBuf::from(DIRNAME));
+
+        // Other tests change the locale too, but never to "C", so the catalog is always used.
+        TextDomain::new("embedded_text_domain")
+            .locale("fr_FR")
+            .skip_system_data_paths()
+            .embedded(catalogs)
+            .init()
+            .unwrap();
+        assert_eq!(
+            domain_directory("embedded_text_domain").unwrap(),
+            PathBuf::from(DIRNAME)
+        );
+        assert_eq!(
+            ::dgettext("embedded_text_domain", "Hello, world!"),
+            "Bonjour, le monde !"
+        );
+    }
+
+    #[test]
+    fn language_directories_are_matched_like_gnu_gettext_does() {
+        let find = |language| {
+            let mut searched =
+                SearchedPath::new(PathBuf::from("/data"), &Layout::new(DEFAULT_LAYOUT));
+            searched.locale_dir_exists = true;
+            let dirs = ["pt", "pt_BR", "en_GB", "sr@latin", "de_DE.utf8"];
+            let mo_path = find_mo_file(&mut searched, language, |variant| {
+                Some(Path::new("/data/locale").join(variant)).filter(|_| dirs.contains(&variant))
+            });
+            (mo_path, searched.language_dirs)
+        };
+
+        let found = |language: &str| Some(Path::new("/data/locale").join(language));
+        assert_eq!(find("pt_BR"), (found("pt_BR"), vec!["pt_BR".to_owned()]));
+        assert_eq!(
+            find("pt_PT.UTF-8"),
+            (
+                found("pt"),
+                vec![
+                    "pt_PT.UTF-8".to_owned(),
+                    "pt_PT.utf8".to_owned(),
+                    "pt_PT".to_owned(),
+                    "pt.UTF-8".to_owned(),
+                    "pt.utf8".to_owned(),
+                    "pt".to_owned()
+                ]
+            )
+        );
+        assert_eq!(find("sr_RS@latin").0, found("sr@latin"));
+        assert_eq!(find("de_DE.UTF-8@euro").0, found("de_DE.utf8"));
+        assert_eq!(find("sr"), (None, vec!["sr".to_owned()]));
+        assert_eq!(find("en_US").0, None);
+        assert_eq!(find("en").0, None);
+    }
+
+    #[test]
+    fn searches_are_reported() {
+        let temp_dir = TempDir::new("text-domain");
+        let data_dir = temp_dir.path();
+        fs::create_dir_all(data_dir.join("locale").join("pt_BR")).unwrap();
+        let missing_dir = data_dir.join("missing");
+
+        let (result, report) = TextDomain::new("0_0")
+            .locale("pt_BR")
+            .skip_system_data_paths()
+            .prepend(&missing_dir)
+            .push(data_dir)
+            .init_with_report();
+        match result {
+            Err(TextDomainError::TranslationNotFound(message)) => assert_eq!(message, "pt"),
+            _ => panic!(),
+        }
+        assert_eq!(report.mo_path, None);
+        assert_eq!(report.paths.len(), 2);
+        assert_eq!(report.paths[0].path, missing_dir);
+        assert!(!report.paths[0].locale_dir_exists);
+        assert!(report.paths[0].language_dirs.is_empty());
+        assert_eq!(report.paths[1].locale_dir, data_dir.join("locale"));
+        assert!(report.paths[1].locale_dir_exists);
+        assert!(report.paths[1]
+            .language_dirs
+            .ends_with(&["pt_BR".to_owned(), "pt".to_owned()]));
+        assert!(report.to_string().ends_with("no translation found"));
+    }
+
+    #[test]
+    fn data_home_defaults_to_local_share() {
+        assert_eq!(
+            data_home(Some("/data/home".to_owned()), Some("/home/user".into())),
+            Some(PathBuf::from("/data/home"))
+        );
+        assert_eq!(
+            data_home(Some("relative".to_owned()), Some("/home/user".into())),
+            Some(PathBuf::from("/home/user/.local/share"))
+        );
+        assert_eq!(
+            data_home(None, Some("/home/user".into())),
+            Some(PathBuf::from("/home/user/.local/share"))
+        );
+        assert_eq!(data_home(None, Some("".into())), None);
+        assert_eq!(data_home(None, None), None);
+
+        assert_eq!(
+            data_dirs(None),
+            vec![
+                PathBuf::from("/usr/local/share/"),
+                PathBuf::from("/usr/share/")
+            ]
+        );
+        assert_eq!(
+            data_dirs(Some("/opt/share::/usr/share".to_owned())),
+            vec![PathBuf::from("/opt/share"), PathBuf::from("/usr/share")]
+        );
+    }
+
+    #[test]
+    fn exe_relative_paths_are_searched_after_prepended_ones() {
+        let exe_dir = env::current_exe().unwrap().parent().unwrap().to_owned();
+        let paths = TextDomain::new("test")
+            .skip_system_data_paths()
+            .push("post")
+            .exe_relative("../share")
+            .prepend("pre")
+            .search_paths()
+            .into_iter()
+            .map(|searched| searched.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("pre"),
+                exe_dir.join("../share"),
+                PathBuf::from("post")
+            ]
+        );
+    }
+
+    #[test]
+    fn catalogs_are_resolved_without_side_effects() {
+        let temp_dir = TempDir::new("resolve");
+        let temp_dir = temp_dir.path();
+        let first_dir = temp_dir.join("first");
+        let second_dir = temp_dir.join("second");
+        let mo_rel_path = Path::new("LC_MESSAGES").join("resolve_test.mo");
+        create_empty_files(&[
+            first_dir.join("locale").join("pt").join(&mo_rel_path),
+            first_dir.join("locale").join("fr").join(&mo_rel_path),
+            first_dir
+                .join("locale")
+                .join("de")
+                .join("LC_MESSAGES")
+                .join("other.mo"),
+            second_dir.join("locale").join("pt").join(&mo_rel_path),
+            second_dir.join("locale").join("es").join(&mo_rel_path),
+        ]);
+
+        let text_domain = TextDomain::new("resolve_test")
+            .locale("pt_BR")
+            .skip_system_data_paths()
+            .push(&first_dir)
+            .push(&second_dir);
+
+        let catalog = text_domain.resolve().unwrap().unwrap();
+        assert_eq!(catalog.language, "pt");
+        assert_eq!(catalog.domain, "resolve_test");
+        assert_eq!(
+            catalog.path,
+            first_dir.join("locale").join("pt").join(&mo_rel_path)
+        );
+        assert_eq!(catalog.dirname, first_dir.join("locale"));
+        assert_ne!(
+            getters::domain_directory("resolve_test").unwrap(),
+            catalog.dirname
+        );
+
+        let languages = text_domain
+            .available_languages()
+            .into_iter()
+            .map(|catalog| (catalog.language, catalog.dirname))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            languages,
+            vec![
+                ("fr".to_owned(), first_dir.join("locale")),
+                ("pt".to_owned(), first_dir.join("locale")),
+                ("es".to_owned(), second_dir.join("locale")),
+            ]
+        );
+
+        assert_eq!(
+            TextDomain::new("resolve_test")
+                .locale("C")
+                .resolve()
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn catalogs_are_searched_for_in_the_category_directory() {
+        let temp_dir = TempDir::new("category");
+        let temp_dir = temp_dir.path();
+        let catalog = |language, category| {
+            temp_dir
+                .join("locale")
+                .join(language)
+                .join(category)
+                .join("category_test.mo")
+        };
+        create_empty_files(&[catalog("pt", "LC_TIME"), catalog("pt_BR", "LC_MESSAGES")]);
+        let text_domain = || {
+            TextDomain::new("category_test")
+                .locale("pt_BR")
+                .skip_system_data_paths()
+                .push(temp_dir)
+        };
+
+        let found = text_domain()
+            .locale_category(LocaleCategory::LcTime)
+            .resolve()
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.language, "pt");
+        assert_eq!(found.category, LocaleCategory::LcTime);
+        assert_eq!(found.path, catalog("pt", "LC_TIME"));
+
+        // `LC_ALL` has no directory of its own.
+        let text_domain = text_domain()
+            .locale_category(LocaleCategory::LcAll)
+            .search_category(LocaleCategory::LcTime)
+            .search_category(LocaleCategory::LcMonetary)
+            .search_category(LocaleCategory::LcAll);
+        let found = text_domain.resolve().unwrap().unwrap();
+        assert_eq!(found.category, LocaleCategory::LcMessages);
+        assert_eq!(found.path, catalog("pt_BR", "LC_MESSAGES"));
+
+        let mut report = SearchReport {
+            languages: vec!["pt_BR".to_owned()],
+            ..SearchReport::default()
+        };
+        let found = text_domain
+            .search("category_test", &[], &mut report)
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.language, found.category))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                ("pt_BR".to_owned(), LocaleCategory::LcMessages),
+                ("pt".to_owned(), LocaleCategory::LcTime),
+            ]
+        );
+    }
+
+    #[test]
+    fn catalogs_are_searched_for_in_custom_layouts() {
+        let temp_dir = TempDir::new("text-domain-layout");
+        let temp_dir = temp_dir.path();
+        let flat_dir = temp_dir.join("i18n");
+        let nested_dir = temp_dir.join("share").join("layout_test").join("locale");
+        create_empty_files(&[
+            flat_dir.join("layout_test-fr.mo"),
+            flat_dir.join("layout_test-pt_BR.mo"),
+            flat_dir.join("other-de.mo"),
+            nested_dir
+                .join("es")
+                .join("LC_MESSAGES")
+                .join("layout_test.mo"),
+        ]);
+
+        let flat = TextDomain::new("layout_test")
+            .locale("fr_FR")
+            .skip_system_data_paths()
+            .push(temp_dir)
+            .layout("i18n/{domain}-{language}.mo");
+        let found = flat.resolve().unwrap().unwrap();
+        assert_eq!(found.language, "fr");
+        assert_eq!(found.path, flat_dir.join("layout_test-fr.mo"));
+        assert_eq!(found.dirname, flat_dir);
+        let languages = flat
+            .available_languages()
+            .into_iter()
+            .map(|catalog| catalog.language)
+            .collect::<Vec<_>>();
+        assert_eq!(languages, vec!["fr", "pt_BR"]);
+
+        // libintl can't find catalogs in such a layout, but the pure-Rust backend is given the
+        // path.
+        #[cfg(feature = "pure-rust")]
+        {
+            flat.init().unwrap();
+            assert_eq!(getters::domain_directory("layout_test").unwrap(), flat_dir);
+        }
+
+        // Layouts that end like GNU gettext's are bound to the directory before the language.
+        let found = TextDomain::new("layout_test")
+            .locale("es")
+            .skip_system_data_paths()
+            .push(temp_dir)
+            .layout("share/{domain}/locale/{language}/{category}/{domain}.mo")
+            .resolve()
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.dirname, nested_dir);
+    }
+
+    #[test]
+    fn secondary_domains_are_bound() {
+        let temp_dir = TempDir::new("secondary");
+        let temp_dir = temp_dir.path();
+        let main_dir = temp_dir.join("main");
+        let plugin_dir = temp_dir.join("plugin");
+        let catalog = |dir: &Path, domainname| {
+            dir.join("locale")
+                .join("pt")
+                .join("LC_MESSAGES")
+                .join(format!("{}.mo", domainname))
+        };
+        create_empty_files(&[
+            catalog(&main_dir, "secondary_main"),
+            catalog(&main_dir, "secondary_library"),
+            catalog(&plugin_dir, "secondary_plugin"),
+        ]);
+
+        let (result, report) = TextDomain::new("secondary_main")
+            .locale("pt_BR")
+            .skip_system_data_paths()
+            .push(&main_dir)
+            .secondary_domain("secondary_plugin", vec![&plugin_dir])
+            .secondary_domain("secondary_library", Vec::<PathBuf>::new())
+            .secondary_domain("secondary_missing", vec![&plugin_dir])
+            .init_with_report();
+        let outcome = result.unwrap();
+        assert_eq!(outcome.language.as_deref(), Some("pt"));
+        assert_eq!(outcome.dirname, Some(main_dir.join("locale")));
+        assert_eq!(outcome.domain, "secondary_main");
+
+        let found = report
+            .secondary_domains
+            .iter()
+            .map(|(domainname, report)| (domainname.as_str(), report.mo_path.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "secondary_plugin",
+                    Some(catalog(&plugin_dir, "secondary_plugin"))
+                ),
+                (
+                    "secondary_library",
+                    Some(catalog(&main_dir, "secondary_library"))
+                ),
+                ("secondary_missing", None),
+            ]
+        );
+        assert_eq!(report.secondary_domains[2].1.paths.len(), 2);
+
+        assert_eq!(
+            getters::domain_directory("secondary_plugin").unwrap(),
+            plugin_dir.join("locale")
+        );
+        assert_eq!(
+            getters::domain_directory("secondary_library").unwrap(),
+            main_dir.join("locale")
+        );
+        assert_eq!(
+            getters::textdomain_codeset("secondary_plugin").unwrap(),
+            Some("UTF-8".to_owned())
+        );
+    }
+
+    #[test]
+    fn library_mode_only_binds_the_domain() {
+        let temp_dir = TempDir::new("library");
+        let temp_dir = temp_dir.path();
+        let mo_path = temp_dir
+            .join("locale")
+            .join("pt")
+            .join("LC_MESSAGES")
+            .join("library_test.mo");
+        create_empty_files(&[&mo_path]);
+
+        let result = TextDomain::new("library_test")
+            .locale("pt_BR")
+            .skip_system_data_paths()
+            .push(temp_dir)
+            .library_mode()
+            .init();
+        let outcome = result.unwrap();
+        assert_eq!(outcome.requested_locale.as_deref(), Some("pt_BR"));
+        assert_eq!(outcome.effective_locale, None);
+        assert!(!outcome.setlocale_failed);
+        assert_eq!(outcome.language.as_deref(), Some("pt"));
+        assert_eq!(outcome.dirname, Some(temp_dir.join("locale")));
+        assert_eq!(outcome.domain, "library_test");
+        assert_eq!(outcome.codeset.as_deref(), Some("UTF-8"));
+        assert!(outcome.to_string().contains("locale: unchanged"));
+        assert_eq!(
+            getters::domain_directory("library_test").unwrap(),
+            temp_dir.join("locale")
+        );
+        assert_eq!(
+            getters::textdomain_codeset("library_test").unwrap(),
+            Some("UTF-8".to_owned())
+        );
+        assert_ne!(getters::current_textdomain().unwrap(), b"library_test");
+    }
 }
@@ -0,0 +1,215 @@
+//! Switching the locale of a single thread.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::io;
+use std::ptr;
+
+use super::last_error;
+use ffi;
+
+thread_local! {
+    /// The locales of the live guards of this thread, in the order they were created, each with
+    /// the locale that is restored when the guard is dropped.
+    static GUARDS: RefCell<Vec<(ffi::locale_t, ffi::locale_t)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Makes the calling thread use another locale until the guard is dropped.
+///
+/// [`setlocale`] changes the locale of the whole process, so it can't be used to, say, answer
+/// requests from different users in their own languages at the same time. GNU gettext looks
+/// messages up in the locale of the calling thread, which is set with [uselocale(3)][], so a
+/// `LocaleGuard` creates a locale object with [newlocale(3)][], makes the thread use it, and
+/// restores the previous one when dropped:
+///
+/// ```no_run
+/// use gettextrs::{gettext, LocaleGuard};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// {
+///     let _guard = LocaleGuard::new("de_DE.UTF-8")?;
+///     println!("{}", gettext("Hello, world!"));
+/// }
+/// // Back to the previous locale.
+/// println!("{}", gettext("Hello, world!"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// All categories are switched, not only `LC_MESSAGES`, so that the messages are converted to the
+/// codeset of the new locale, too (unless another one is set with [`bind_textdomain_codeset`]).
+///
+/// Guards can be nested. They're usually dropped in the reverse order of their creation, as scoped
+/// guards are, but don't have to be: a guard that is dropped before the ones created after it
+/// hands the locale it replaced over to the next one, so the thread keeps using the locale of the
+/// newest guard, and gets its original locale back once all of them are dropped. The guard can't
+/// be sent to another thread, since it only affects the thread that created it.
+///
+/// With the system libintl, this is only available on platforms whose C library is known to
+/// provide these functions: Linux, macOS, iOS, FreeBSD, and DragonFly BSD.
+///
+/// [`setlocale`]: fn.setlocale.html
+/// [`bind_textdomain_codeset`]: fn.bind_textdomain_codeset.html
+/// [uselocale(3)]: https://www.man7.org/linux/man-pages/man3/uselocale.3.html
+/// [newlocale(3)]: https://www.man7.org/linux/man-pages/man3/newlocale.3.html
+#[derive(Debug)]
+pub struct LocaleGuard {
+    locale: ffi::locale_t,
+}
+
+impl LocaleGuard {
+    /// Switches the calling thread to `locale`. As with [`setlocale`], an empty name selects the
+    /// locale from the environment variables.
+    ///
+    /// Returns an error if the locale can't be created, e.g. because it's not installed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `locale` contains an internal 0 byte, as such values can't be passed to the
+    /// underlying C API.
+    ///
+    /// [`setlocale`]: fn.setlocale.html
+    pub fn new<T: Into<Vec<u8>>>(locale: T) -> Result<LocaleGuard, io::Error> {
+        let locale = CString::new(locale).expect("`locale` contains an internal 0 byte");
+        unsafe {
+            let locale = ffi::newlocale(ffi::LC_ALL_MASK, locale.as_ptr(), ptr::null_mut());
+            if locale.is_null() {
+                return Err(last_error());
+            }
+
+            let previous = ffi::uselocale(locale);
+            if previous.is_null() {
+                let error = last_error();
+                ffi::freelocale(locale);
+                return Err(error);
+            }
+
+            GUARDS.with(|guards| guards.borrow_mut().push((locale, previous)));
+            Ok(LocaleGuard { locale })
+        }
+    }
+}
+
+impl Drop for LocaleGuard {
+    fn drop(&mut self) {
+        GUARDS.with(|guards| {
+            let mut guards = guards.borrow_mut();
+            let index = guards
+                .iter()
+                .position(|&(locale, _)| locale == self.locale)
+                .expect("the guard is not registered");
+            let (_, previous) = guards.remove(index);
+            unsafe {
+                match guards.get_mut(index) {
+                    // Dropped out of order: the next guard now restores our previous locale.
+                    Some(next) => next.1 = previous,
+                    None => {
+                        ffi::uselocale(previous);
+                    }
+                }
+                ffi::freelocale(self.locale);
+            }
+        });
+    }
+}
+
+/// Calls `f` with the calling thread switched to `locale`, then switches it back. See
+/// [`LocaleGuard`].
+///
+/// ```no_run
+/// use gettextrs::{gettext, with_locale};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let greeting = with_locale("de_DE.UTF-8", || gettext("Hello, world!"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `locale` contains an internal 0 byte, as such values can't be passed to the
+/// underlying C API.
+///
+/// [`LocaleGuard`]: struct.LocaleGuard.html
+pub fn with_locale<T, F, R>(locale: T, f: F) -> Result<R, io::Error>
+where
+    T: Into<Vec<u8>>,
+    F: FnOnce() -> R,
+{
+    let _guard = LocaleGuard::new(locale)?;
+    Ok(f())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current_locale() -> ffi::locale_t {
+        unsafe { ffi::uselocale(ptr::null_mut()) }
+    }
+
+    #[test]
+    fn previous_locale_is_restored() {
+        let before = current_locale();
+        with_locale("C", || {
+            assert_ne!(current_locale(), before);
+            with_locale("POSIX", || ()).unwrap();
+        })
+        .unwrap();
+        assert_eq!(current_locale(), before);
+    }
+
+    #[test]
+    fn guards_dropped_out_of_order_restore_the_original_locale() {
+        let before = current_locale();
+        let first = LocaleGuard::new("C").unwrap();
+        let second = LocaleGuard::new("POSIX").unwrap();
+        let third = LocaleGuard::new("C").unwrap();
+
+        drop(second);
+        assert_eq!(current_locale(), third.locale);
+        drop(first);
+        assert_eq!(current_locale(), third.locale);
+        drop(third);
+        assert_eq!(current_locale(), before);
+    }
+
+    #[cfg(not(feature = "pure-rust"))]
+    #[test]
+    fn unknown_locales_are_rejected() {
+        assert!(LocaleGuard::new("xx_NOWHERE.UTF-8").is_err());
+    }
+
+    #[cfg(feature = "pure-rust")]
+    #[test]
+    fn messages_are_looked_up_in_thread_locale() {
+        use dcgettext;
+        use std::fs;
+        use test_utils::TempDir;
+        use LocaleCategory;
+
+        let temp_dir = TempDir::new("thread-locale");
+        let dir = temp_dir.path();
+        let path = dir.join("thread_locale_test.mo");
+        fs::write(
+            &path,
+            ffi::mo_file(&[("Hello, world!", "Hallo, Welt!")], false, true),
+        )
+        .unwrap();
+        ffi::bind_catalog(
+            b"thread_locale_test",
+            LocaleCategory::LcMessages as i32,
+            &path,
+        );
+
+        let lookup = || {
+            dcgettext(
+                "thread_locale_test",
+                "Hello, world!",
+                LocaleCategory::LcMessages,
+            )
+        };
+        assert_eq!(with_locale("de_DE", lookup).unwrap(), "Hallo, Welt!");
+        assert_eq!(with_locale("C", lookup).unwrap(), "Hello, world!");
+    }
+}
@@ -0,0 +1,24 @@
+extern crate gettextrs;
+
+use gettextrs::TextDomain;
+use std::env;
+
+// The test changes the environment, so it runs in a binary of its own.
+#[test]
+fn c_locale_from_environment_needs_no_translation() {
+    env::remove_var("LANGUAGE");
+    env::set_var("LC_ALL", "C");
+
+    let text_domain = TextDomain::new("c_locale_test").skip_system_data_paths();
+    assert_eq!(text_domain.resolve().unwrap(), None);
+
+    let outcome = TextDomain::new("c_locale_test")
+        .skip_system_data_paths()
+        .init()
+        .unwrap();
+    assert_eq!(outcome.requested_locale, None);
+    assert_eq!(outcome.effective_locale, Some(b"C".to_vec()));
+    assert_eq!(outcome.language, None);
+    assert_eq!(outcome.dirname, None);
+    assert_eq!(outcome.codeset, None);
+}
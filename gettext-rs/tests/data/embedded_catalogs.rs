@@ -0,0 +1,6 @@
+// The same format as the files written by `gettext_tools::build::embed`, except that the path to
+// the catalog is relative.
+&[EmbeddedCatalog {
+    path: "en/LC_MESSAGES/embedded_domain.mo",
+    data: include_bytes!("embedded.mo"),
+}]
@@ -0,0 +1,62 @@
+extern crate gettextrs;
+
+use gettextrs::getters::*;
+use gettextrs::guards::*;
+use gettextrs::LocaleCategory;
+use std::path::Path;
+
+// The guards change global settings, so they're all tested one after another, in a binary of their
+// own.
+#[test]
+fn guards_restore_previous_settings() {
+    let domain = current_textdomain().unwrap();
+    {
+        let guard = TextDomainGuard::new("guarded_domain").unwrap();
+        assert_eq!(guard.previous(), &domain[..]);
+        assert_eq!(current_textdomain().unwrap(), b"guarded_domain");
+        {
+            let _inner = TextDomainGuard::new("inner_domain").unwrap();
+            assert_eq!(current_textdomain().unwrap(), b"inner_domain");
+        }
+        assert_eq!(current_textdomain().unwrap(), b"guarded_domain");
+    }
+    assert_eq!(current_textdomain().unwrap(), domain);
+
+    let directory = domain_directory("guarded_domain").unwrap();
+    {
+        let guard = BindTextDomainGuard::new("guarded_domain", "/opt/guarded/locale").unwrap();
+        assert_eq!(guard.previous(), directory);
+        assert_eq!(
+            domain_directory("guarded_domain").unwrap(),
+            Path::new("/opt/guarded/locale")
+        );
+    }
+    assert_eq!(domain_directory("guarded_domain").unwrap(), directory);
+
+    let messages = current_locale(LocaleCategory::LcMessages).unwrap();
+    {
+        let _guard = SetLocaleGuard::new(LocaleCategory::LcMessages, "C.UTF-8").unwrap();
+        assert_eq!(
+            current_locale(LocaleCategory::LcMessages).unwrap(),
+            b"C.UTF-8"
+        );
+
+        // With categories in different locales, `LC_ALL` is a list that has to be restored as a
+        // whole.
+        let all = current_locale(LocaleCategory::LcAll).unwrap();
+        {
+            let guard = SetLocaleGuard::new(LocaleCategory::LcAll, "C").unwrap();
+            assert_eq!(guard.previous(), &all[..]);
+            assert_eq!(current_locale(LocaleCategory::LcMessages).unwrap(), b"C");
+        }
+        assert_eq!(current_locale(LocaleCategory::LcAll).unwrap(), all);
+        assert_eq!(
+            current_locale(LocaleCategory::LcMessages).unwrap(),
+            b"C.UTF-8"
+        );
+    }
+    assert_eq!(
+        current_locale(LocaleCategory::LcMessages).unwrap(),
+        messages
+    );
+}
@@ -120,6 +120,14 @@ fn ngettext_fn() {
         ngettext("Hello, World!", "Hello, Worlds!", 2),
         "Hello, Worlds!"
     );
+    assert_eq!(
+        ngettext("Hello, World!", "Hello, Worlds!", 4_294_967_297u64),
+        "Hello, Worlds!"
+    );
+    assert_eq!(
+        ngettext("Hello, World!", "Hello, Worlds!", 1usize),
+        "Hello, World!"
+    );
 }
 
 #[test]
@@ -190,3 +198,132 @@ fn npgettext_fn() {
         "Hello, Worlds!"
     );
 }
+
+#[test]
+fn dpgettext_fn() {
+    let _ = *SETUP;
+
+    assert_eq!(
+        dpgettext("bound_domain", "context", "Hello, World!"),
+        "Hello, World!"
+    );
+}
+
+#[test]
+fn dcpgettext_fn() {
+    let _ = *SETUP;
+
+    assert_eq!(
+        dcpgettext(
+            "bound_domain",
+            "context",
+            "Hello, World!",
+            LocaleCategory::LcMessages
+        ),
+        "Hello, World!"
+    );
+}
+
+#[test]
+fn dnpgettext_fn() {
+    let _ = *SETUP;
+
+    assert_eq!(
+        dnpgettext(
+            "bound_domain",
+            "context",
+            "Hello, World!",
+            "Hello, Worlds!",
+            1
+        ),
+        "Hello, World!"
+    );
+    assert_eq!(
+        dnpgettext(
+            "bound_domain",
+            "context",
+            "Hello, World!",
+            "Hello, Worlds!",
+            2
+        ),
+        "Hello, Worlds!"
+    );
+}
+
+#[test]
+fn dcnpgettext_fn() {
+    let _ = *SETUP;
+
+    assert_eq!(
+        dcnpgettext(
+            "bound_domain",
+            "context",
+            "Hello, World!",
+            "Hello, Worlds!",
+            1,
+            LocaleCategory::LcMessages
+        ),
+        "Hello, World!"
+    );
+    assert_eq!(
+        dcnpgettext(
+            "bound_domain",
+            "context",
+            "Hello, World!",
+            "Hello, Worlds!",
+            2,
+            LocaleCategory::LcMessages
+        ),
+        "Hello, Worlds!"
+    );
+}
+
+#[test]
+fn domain_handle_works() {
+    let _ = *SETUP;
+
+    let domain = Domain::new("bound_domain");
+    assert_eq!(domain.name().to_bytes(), b"bound_domain");
+    assert_eq!(domain.gettext("Hello, World!"), "Hello, World!");
+    assert_eq!(
+        domain.ngettext("Hello, World!", "Hello, Worlds!", 1),
+        "Hello, World!"
+    );
+    assert_eq!(
+        domain.ngettext("Hello, World!", "Hello, Worlds!", 2),
+        "Hello, Worlds!"
+    );
+    assert_eq!(domain.pgettext("context", "Hello, World!"), "Hello, World!");
+    assert_eq!(
+        domain.npgettext("context", "Hello, World!", "Hello, Worlds!", 2),
+        "Hello, Worlds!"
+    );
+    assert_eq!(
+        domain
+            .with_category(LocaleCategory::LcMessages)
+            .gettext("Hello, World!"),
+        "Hello, World!"
+    );
+}
+
+#[cfg(feature = "pure-rust")]
+#[test]
+fn embedded_catalogs_are_used() {
+    let _ = *SETUP;
+
+    let catalogs = gettextrs::include_catalogs!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/embedded_catalogs.rs"
+    ));
+    embedded::register(catalogs);
+
+    // `SETUP` sets the locale to en_US.UTF-8.
+    assert_eq!(
+        dgettext("embedded_domain", "Hello, world!"),
+        "Hello, embedded world!"
+    );
+    assert_eq!(
+        dgettext("embedded_domain", "Not translated"),
+        "Not translated"
+    );
+}
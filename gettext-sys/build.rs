@@ -85,6 +85,9 @@ fn check_dependencies(required_programs: Vec<&str>) {
 fn main() {
     let target = env::var("TARGET").unwrap();
 
+    // Set when the bundled libintl is built, which supports relocation
+    println!("cargo:rustc-check-cfg=cfg(gettext_relocatable)");
+
     if try_gettext_system() {
         return;
     }
@@ -208,6 +211,9 @@ fn build_from_source() -> bool {
     copy_artifacts_to_out_dir(&build_dir);
     set_up_linking_with_out_dir(&target);
 
+    println!("cargo:rustc-cfg=gettext_relocatable");
+    println!("cargo:relocatable=1");
+
     true
 }
 
@@ -281,6 +287,7 @@ fn run_configure(target: &str, compiler: &cc::Tool, build_dir: &Path) {
     cmd.arg("--disable-shared");
     cmd.arg("--enable-static");
     cmd.arg("--enable-fast-install");
+    cmd.arg("--enable-relocatable");
     cmd.arg("--with-included-gettext");
     cmd.arg("--with-included-glib");
     cmd.arg("--with-included-libcroco");
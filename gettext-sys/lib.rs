@@ -37,7 +37,82 @@ extern "C" {
     pub fn setlocale(category: c_int, locale: *const c_char) -> *mut c_char;
 }
 
+// Only the bundled libintl is built with `--enable-relocatable`; system ones usually aren't.
+#[cfg(gettext_relocatable)]
+extern "C" {
+    /// Makes libintl replace `orig_prefix` with `curr_prefix` at the start of the directories it
+    /// looks for catalogs in, so that a program installed with `orig_prefix` finds its catalogs
+    /// after being moved to `curr_prefix`. Both are directory names without a trailing slash.
+    pub fn libintl_set_relocation_prefix(orig_prefix: *const c_char, curr_prefix: *const c_char);
+}
+
 #[cfg(windows)]
 pub unsafe fn wbindtextdomain(domain: *const c_char, dir: *const wchar_t) -> *mut wchar_t {
     libintl_wbindtextdomain(domain, dir)
 }
+
+// Per-thread locales. These come from the C library rather than from libintl, but GNU gettext
+// looks messages up in the locale set by `uselocale`, so they're exposed here for switching the
+// language of a single thread. Windows has no equivalent.
+#[cfg(unix)]
+#[allow(non_camel_case_types)]
+pub type locale_t = *mut std::os::raw::c_void;
+
+#[cfg(unix)]
+extern "C" {
+    pub fn newlocale(category_mask: c_int, locale: *const c_char, base: locale_t) -> locale_t;
+    pub fn uselocale(locale: locale_t) -> locale_t;
+    pub fn freelocale(locale: locale_t);
+}
+
+#[cfg(unix)]
+pub const LC_GLOBAL_LOCALE: locale_t = -1isize as locale_t;
+
+// Masks for `newlocale`. Their values differ between C libraries, so they're only defined for
+// the ones that are known.
+#[cfg(target_os = "linux")]
+pub const LC_CTYPE_MASK: c_int = 1 << 0;
+#[cfg(target_os = "linux")]
+pub const LC_NUMERIC_MASK: c_int = 1 << 1;
+#[cfg(target_os = "linux")]
+pub const LC_TIME_MASK: c_int = 1 << 2;
+#[cfg(target_os = "linux")]
+pub const LC_COLLATE_MASK: c_int = 1 << 3;
+#[cfg(target_os = "linux")]
+pub const LC_MONETARY_MASK: c_int = 1 << 4;
+#[cfg(target_os = "linux")]
+pub const LC_MESSAGES_MASK: c_int = 1 << 5;
+#[cfg(all(target_os = "linux", not(target_env = "musl")))]
+pub const LC_ALL_MASK: c_int = 0x1fbf;
+#[cfg(all(target_os = "linux", target_env = "musl"))]
+pub const LC_ALL_MASK: c_int = 0x7fffffff;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_COLLATE_MASK: c_int = 1 << 0;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_CTYPE_MASK: c_int = 1 << 1;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_MESSAGES_MASK: c_int = 1 << 2;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_MONETARY_MASK: c_int = 1 << 3;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_NUMERIC_MASK: c_int = 1 << 4;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_TIME_MASK: c_int = 1 << 5;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub const LC_ALL_MASK: c_int = 0x3f;
+
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_COLLATE_MASK: c_int = 1 << 0;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_CTYPE_MASK: c_int = 1 << 1;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_MONETARY_MASK: c_int = 1 << 2;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_NUMERIC_MASK: c_int = 1 << 3;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_TIME_MASK: c_int = 1 << 4;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_MESSAGES_MASK: c_int = 1 << 5;
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+pub const LC_ALL_MASK: c_int = 0x3f;
@@ -0,0 +1,129 @@
+//! Extracts translatable strings from Rust sources into a POT file.
+//!
+//! Run with `--help` for usage.
+
+extern crate gettext_tools;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+use gettext_tools::extract::{Extractor, Keyword, PotHeader};
+
+const USAGE: &str = "\
+Usage: xgettext-rs [OPTION]... PATH...
+
+Extracts translatable strings from Rust source files, and from all .rs files in
+directories, into a POT file.
+
+Options:
+  -o, --output=FILE            write output to FILE instead of standard output
+  -k, --keyword=WORD:SPEC      look for WORD as an additional keyword; SPEC
+                               is like in xgettext, e.g. npgettext:1c,2,3
+  -k, --keyword                don't use default keywords
+  -c, --add-comments=TAG       extract comments starting with TAG (default:
+                               TRANSLATORS:)
+      --no-comments            don't extract comments
+      --package-name=NAME      set package name in the header
+      --package-version=VER    set package version in the header
+      --msgid-bugs-address=ADDR
+                               set the address for reporting msgid bugs
+      --copyright-holder=NAME  set the copyright holder in the header
+  -h, --help                   display this help and exit";
+
+fn fail(message: &str) -> ! {
+    eprintln!("xgettext-rs: {}", message);
+    eprintln!("Try 'xgettext-rs --help' for more information.");
+    process::exit(1);
+}
+
+fn main() {
+    let mut extractor = Extractor::new();
+    let mut output = None;
+    let mut header = PotHeader::default();
+    let mut paths = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            paths.push(arg);
+            continue;
+        }
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            return;
+        }
+        if arg == "-k" || arg == "--keyword" {
+            extractor = extractor.no_keywords();
+            continue;
+        }
+        if arg == "--no-comments" {
+            extractor = extractor.comment_tag(None::<String>);
+            continue;
+        }
+
+        let (name, value) = match arg.find('=') {
+            Some(position) => (arg[..position].to_owned(), arg[position + 1..].to_owned()),
+            None if arg.starts_with("--") => match args.next() {
+                Some(value) => (arg, value),
+                None => fail(&format!("option '{}' requires an argument", arg)),
+            },
+            None if arg.len() > 2 => (arg[..2].to_owned(), arg[2..].to_owned()),
+            None => match args.next() {
+                Some(value) => (arg, value),
+                None => fail(&format!("option '{}' requires an argument", arg)),
+            },
+        };
+        match name.as_str() {
+            "-o" | "--output" => output = Some(value),
+            "-k" | "--keyword" => match Keyword::parse(&value) {
+                Ok(keyword) => extractor = extractor.keyword(keyword),
+                Err(error) => fail(&error.to_string()),
+            },
+            "-c" | "--add-comments" => extractor = extractor.comment_tag(Some(value)),
+            "--package-name" => header.package_name = Some(value),
+            "--package-version" => header.package_version = Some(value),
+            "--msgid-bugs-address" => header.msgid_bugs_address = Some(value),
+            "--copyright-holder" => header.copyright_holder = Some(value),
+            _ => fail(&format!("unrecognized option '{}'", name)),
+        }
+    }
+
+    if paths.is_empty() {
+        fail("no input files given");
+    }
+
+    for path in &paths {
+        let result = if Path::new(path).is_dir() {
+            extractor.extract_dir(path)
+        } else {
+            extractor.extract_file(path)
+        };
+        if let Err(error) = result {
+            eprintln!("xgettext-rs: {}: {}", path, error);
+            process::exit(1);
+        }
+    }
+
+    let result = match output.as_deref() {
+        None | Some("-") => {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            extractor
+                .write_pot(&mut out, &header)
+                .and_then(|_| out.flush())
+        }
+        Some(path) => File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            extractor
+                .write_pot(&mut out, &header)
+                .and_then(|_| out.flush())
+        }),
+    };
+    if let Err(error) = result {
+        eprintln!("xgettext-rs: can't write output: {}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,632 @@
+//! Compilation of translations from build scripts.
+//!
+//! This does what `msgfmt` does, but without requiring GNU gettext to be installed on the build
+//! machine. Put the PO files into the `po` directory of your crate, named after their languages
+//! (e.g. `po/fr.po`, `po/pt_BR.po`), and add this crate to the build dependencies:
+//!
+//! ```toml
+//! [build-dependencies]
+//! gettext-tools = "0.1"
+//! ```
+//!
+//! Then compile the translations in `build.rs`:
+//!
+//! ```rust,no_run
+//! extern crate gettext_tools;
+//!
+//! use gettext_tools::build::Catalogs;
+//!
+//! fn main() {
+//!     Catalogs::new("hellorust").compile().unwrap();
+//! }
+//! ```
+//!
+//! The catalogs end up in `$OUT_DIR/locale/<language>/LC_MESSAGES/<domain>.mo`, which is the
+//! layout that gettext-rs's `TextDomain` looks for, so the program can find them like this:
+//!
+//! ```rust,ignore
+//! TextDomain::new("hellorust").push(env!("OUT_DIR")).init()?;
+//! ```
+//!
+//! As with `msgfmt`, fuzzy and untranslated entries are left out of the catalogs. Fuzzy entries
+//! are reported as Cargo warnings, as they usually need a translator's attention; so are obsolete
+//! (`#~`) entries, which are ignored.
+//!
+//! # Environment variables
+//!
+//! Like the build script of gettext-sys, the following variables are looked up with the target
+//! prefix first (e.g. `X86_64_UNKNOWN_LINUX_GNU_GETTEXT_PO_DIR`), then without it:
+//!
+//! - `GETTEXT_PO_DIR` overrides the directory with PO files;
+//! - `GETTEXT_LINGUAS` overrides the list of languages to compile. Languages are separated with
+//!   whitespace or commas.
+//!
+//! Without `GETTEXT_LINGUAS` or [`Catalogs::languages`], languages are taken from the `LINGUAS`
+//! file in the PO directory, like autotools do. If there's no such file, all PO files in the
+//! directory are compiled.
+//!
+//! # Embedding catalogs
+//!
+//! Programs that are distributed as a single file can embed the catalogs instead: [`embed`] lists
+//! them in a file that gettext-rs's `include_catalogs!` macro includes. This needs the `pure-rust`
+//! feature of gettext-rs.
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mo;
+use po::{self, ParseError};
+
+/// Errors that might come up while compiling the translations.
+#[derive(Debug)]
+pub enum BuildError {
+    /// No output directory was set, and `OUT_DIR` isn't set either, i.e. the code isn't running in
+    /// a build script.
+    MissingOutDir,
+    /// Reading or writing the file failed.
+    Io(PathBuf, io::Error),
+    /// The PO file is malformed.
+    Parse(PathBuf, ParseError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::BuildError::*;
+
+        match self {
+            MissingOutDir => write!(f, "Output directory is not set, and neither is OUT_DIR."),
+            Io(path, inner) => write!(f, "Can't access {}: {}", path.display(), inner),
+            Parse(path, inner) => write!(f, "Can't parse {}: {}", path.display(), inner),
+        }
+    }
+}
+
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use self::BuildError::*;
+
+        match self {
+            MissingOutDir => None,
+            Io(_, inner) => Some(inner),
+            Parse(_, inner) => Some(inner),
+        }
+    }
+}
+
+/// Statistics about a compiled catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledCatalog {
+    /// Language of the catalog, i.e. the name of the PO file without the extension.
+    pub language: String,
+    /// Path to the MO file.
+    pub path: PathBuf,
+    /// Number of messages that went into the catalog, not counting the header.
+    pub translated: usize,
+    /// Number of fuzzy messages. They're only in the catalog if [`Catalogs::use_fuzzy`] is set.
+    pub fuzzy: usize,
+    /// Number of messages without translations.
+    pub untranslated: usize,
+    /// Number of obsolete messages.
+    pub obsolete: usize,
+}
+
+/// A builder that compiles a directory of PO files into MO files.
+#[derive(Debug, Clone)]
+pub struct Catalogs {
+    domain: String,
+    po_dir: PathBuf,
+    out_dir: Option<PathBuf>,
+    languages: Option<Vec<String>>,
+    use_fuzzy: bool,
+}
+
+impl Catalogs {
+    /// Creates a builder that compiles the PO files in the `po` directory into catalogs for the
+    /// given domain.
+    pub fn new<S: Into<String>>(domain: S) -> Catalogs {
+        Catalogs {
+            domain: domain.into(),
+            po_dir: PathBuf::from("po"),
+            out_dir: None,
+            languages: None,
+            use_fuzzy: false,
+        }
+    }
+
+    /// Sets the directory with PO files. Relative paths are relative to the crate root when
+    /// running in a build script.
+    pub fn po_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.po_dir = path.into();
+        self
+    }
+
+    /// Sets the directory to put the catalogs into. Defaults to `$OUT_DIR/locale`.
+    pub fn out_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// Sets the languages to compile, instead of those from the `LINGUAS` file or the PO
+    /// directory.
+    pub fn languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.languages = Some(languages.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Puts fuzzy translations into the catalogs, like `msgfmt --use-fuzzy` does.
+    pub fn use_fuzzy(mut self, use_fuzzy: bool) -> Self {
+        self.use_fuzzy = use_fuzzy;
+        self
+    }
+
+    /// Compiles the catalogs, returning statistics about each of them.
+    ///
+    /// This also tells Cargo to re-run the build script if the PO files or the environment
+    /// variables change, and reports problems with the translations as Cargo warnings.
+    pub fn compile(self) -> Result<Vec<CompiledCatalog>, BuildError> {
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => match env::var_os("OUT_DIR") {
+                Some(out_dir) => PathBuf::from(out_dir).join("locale"),
+                None => return Err(BuildError::MissingOutDir),
+            },
+        };
+        let po_dir = env("GETTEXT_PO_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.po_dir.clone());
+        println!("cargo:rerun-if-changed={}", po_dir.display());
+
+        let languages = self.find_languages(&po_dir)?;
+        if languages.is_empty() {
+            warning(&format!("no translations found in {}", po_dir.display()));
+        }
+
+        languages
+            .into_iter()
+            .map(|language| self.compile_language(&po_dir, &out_dir, language))
+            .collect()
+    }
+
+    fn find_languages(&self, po_dir: &Path) -> Result<Vec<String>, BuildError> {
+        if let Some(languages) = env("GETTEXT_LINGUAS") {
+            return Ok(split_languages(&languages));
+        }
+        if let Some(ref languages) = self.languages {
+            return Ok(languages.clone());
+        }
+
+        let linguas = po_dir.join("LINGUAS");
+        if linguas.is_file() {
+            println!("cargo:rerun-if-changed={}", linguas.display());
+            let contents =
+                fs::read_to_string(&linguas).map_err(|e| BuildError::Io(linguas.clone(), e))?;
+            let languages = contents
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Ok(split_languages(&languages));
+        }
+
+        let entries = fs::read_dir(po_dir).map_err(|e| BuildError::Io(po_dir.to_owned(), e))?;
+        let mut languages = vec![];
+        for entry in entries {
+            let path = entry
+                .map_err(|e| BuildError::Io(po_dir.to_owned(), e))?
+                .path();
+            if path.extension().is_some_and(|extension| extension == "po") {
+                if let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    languages.push(language.to_owned());
+                }
+            }
+        }
+        languages.sort();
+
+        Ok(languages)
+    }
+
+    fn compile_language(
+        &self,
+        po_dir: &Path,
+        out_dir: &Path,
+        language: String,
+    ) -> Result<CompiledCatalog, BuildError> {
+        let po_path = po_dir.join(format!("{}.po", language));
+        println!("cargo:rerun-if-changed={}", po_path.display());
+
+        let source =
+            fs::read_to_string(&po_path).map_err(|e| BuildError::Io(po_path.clone(), e))?;
+        let entries = po::parse(&source).map_err(|e| BuildError::Parse(po_path.clone(), e))?;
+
+        let mut catalog = CompiledCatalog {
+            path: out_dir
+                .join(&language)
+                .join("LC_MESSAGES")
+                .join(format!("{}.mo", self.domain)),
+            language,
+            translated: 0,
+            fuzzy: 0,
+            untranslated: 0,
+            obsolete: 0,
+        };
+        for entry in entries.iter().filter(|entry| !entry.is_header()) {
+            if entry.obsolete {
+                catalog.obsolete += 1;
+            } else if !entry.is_translated() {
+                catalog.untranslated += 1;
+            } else if entry.is_fuzzy() {
+                catalog.fuzzy += 1;
+                if !self.use_fuzzy {
+                    warning(&format!(
+                        "{}:{}: fuzzy translation of {:?} skipped",
+                        po_path.display(),
+                        entry.line,
+                        entry.msgid
+                    ));
+                }
+            } else {
+                catalog.translated += 1;
+            }
+        }
+        if catalog.obsolete > 0 {
+            warning(&format!(
+                "{}: {} obsolete entries ignored",
+                po_path.display(),
+                catalog.obsolete
+            ));
+        }
+        if self.use_fuzzy {
+            catalog.translated += catalog.fuzzy;
+        }
+
+        let included = entries
+            .into_iter()
+            .filter(|entry| mo::should_include(entry, self.use_fuzzy))
+            .collect::<Vec<_>>();
+        let mut contents = vec![];
+        mo::write(&mut contents, &included).expect("writing into a Vec doesn't fail");
+
+        let catalog_dir = catalog.path.parent().expect("catalog path has a parent");
+        fs::create_dir_all(catalog_dir).map_err(|e| BuildError::Io(catalog_dir.to_owned(), e))?;
+        fs::write(&catalog.path, contents).map_err(|e| BuildError::Io(catalog.path.clone(), e))?;
+
+        Ok(catalog)
+    }
+}
+
+/// Name of the file in `OUT_DIR` that [`embed`] writes, and that gettext-rs's `include_catalogs!`
+/// macro includes by default.
+pub const EMBEDDED_CATALOGS_FILE: &str = "gettext_catalogs.rs";
+
+/// Lists the MO files in `locale_dir` (laid out as `language/category/domain.mo`) in
+/// `$OUT_DIR/gettext_catalogs.rs`, so that gettext-rs's `include_catalogs!` macro can embed them
+/// into the program. Returns the paths of the files relative to `locale_dir`, with `/` as the
+/// separator.
+///
+/// The directory can be a `locale` directory that is checked into the repository, or the output
+/// of [`Catalogs`]:
+///
+/// ```rust,no_run
+/// extern crate gettext_tools;
+///
+/// use gettext_tools::build::{self, Catalogs};
+/// use std::env;
+/// use std::path::Path;
+///
+/// fn main() {
+///     Catalogs::new("hellorust").compile().unwrap();
+///     build::embed(Path::new(&env::var("OUT_DIR").unwrap()).join("locale")).unwrap();
+/// }
+/// ```
+///
+/// This also tells Cargo to re-run the build script if the contents of the directory change.
+pub fn embed<P: AsRef<Path>>(locale_dir: P) -> Result<Vec<String>, BuildError> {
+    let out_dir = env::var_os("OUT_DIR").ok_or(BuildError::MissingOutDir)?;
+    let index_path = Path::new(&out_dir).join(EMBEDDED_CATALOGS_FILE);
+    let locale_dir = env::current_dir()
+        .map_err(|e| BuildError::Io(locale_dir.as_ref().to_owned(), e))?
+        .join(locale_dir);
+    println!("cargo:rerun-if-changed={}", locale_dir.display());
+
+    let catalogs = find_catalogs(&locale_dir)?;
+    fs::write(
+        &index_path,
+        embedded_catalogs_index(&locale_dir, &catalogs)?,
+    )
+    .map_err(|e| BuildError::Io(index_path, e))?;
+
+    Ok(catalogs)
+}
+
+/// Returns the paths of `language/category/domain.mo` files relative to `locale_dir`, sorted.
+fn find_catalogs(locale_dir: &Path) -> Result<Vec<String>, BuildError> {
+    let mut catalogs = vec![];
+    for language in sorted_entries(locale_dir)? {
+        if !locale_dir.join(&language).is_dir() {
+            continue;
+        }
+        for category in sorted_entries(&locale_dir.join(&language))? {
+            let category_dir = locale_dir.join(&language).join(&category);
+            if !category_dir.is_dir() {
+                continue;
+            }
+            for file in sorted_entries(&category_dir)? {
+                if file.ends_with(".mo") && category_dir.join(&file).is_file() {
+                    catalogs.push(format!("{}/{}/{}", language, category, file));
+                }
+            }
+        }
+    }
+
+    Ok(catalogs)
+}
+
+/// Returns the names of the entries of `dir` that are valid UTF-8, sorted.
+fn sorted_entries(dir: &Path) -> Result<Vec<String>, BuildError> {
+    let mut names = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| BuildError::Io(dir.to_owned(), e))? {
+        let entry = entry.map_err(|e| BuildError::Io(dir.to_owned(), e))?;
+        if let Ok(name) = entry.file_name().into_string() {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+/// Generates the Rust expression that `include_catalogs!` includes: a slice of `EmbeddedCatalog`,
+/// with `include_bytes!` pointing at the absolute paths of the files.
+fn embedded_catalogs_index(locale_dir: &Path, catalogs: &[String]) -> Result<String, BuildError> {
+    let mut index = String::from("// Generated by gettext-tools.\n&[\n");
+    for catalog in catalogs {
+        let path = catalog
+            .split('/')
+            .fold(locale_dir.to_owned(), |path, component| {
+                path.join(component)
+            });
+        let absolute = path.to_str().ok_or_else(|| {
+            BuildError::Io(
+                path.clone(),
+                io::Error::new(io::ErrorKind::InvalidData, "path is not valid UTF-8"),
+            )
+        })?;
+        index.push_str(&format!(
+            "    EmbeddedCatalog {{\n        path: {:?},\n        data: include_bytes!({:?}),\n    }},\n",
+            catalog, absolute
+        ));
+    }
+    index.push_str("]\n");
+
+    Ok(index)
+}
+
+fn split_languages(languages: &str) -> Vec<String> {
+    languages
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|language| !language.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Reads the environment variable, preferring the one prefixed with the target triple, like the
+/// build script of gettext-sys does.
+fn env(name: &str) -> Option<String> {
+    if let Ok(target) = env::var("TARGET") {
+        let prefixed = format!("{}_{}", target.to_uppercase().replace('-', "_"), name);
+        println!("cargo:rerun-if-env-changed={}", prefixed);
+
+        if let Ok(var) = env::var(&prefixed) {
+            return Some(var);
+        }
+    }
+
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var(name).ok()
+}
+
+fn warning(message: &str) {
+    println!("cargo:warning={}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// A temporary directory that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .subsec_nanos();
+            let path = env::temp_dir().join(format!("gettext-tools-{}-{}", name, nanos));
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const FRENCH: &str = r#"msgid ""
+msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+msgid "Hello"
+msgstr "Bonjour"
+
+#, fuzzy
+msgid "Goodbye"
+msgstr "Au revoir"
+
+msgid "Untranslated"
+msgstr ""
+
+#~ msgid "Obsolete"
+#~ msgstr "Obsolète"
+"#;
+
+    #[test]
+    fn catalogs_are_compiled_into_textdomain_layout() {
+        let dir = TempDir::new("compile");
+        let po_dir = dir.0.join("po");
+        fs::create_dir(&po_dir).unwrap();
+        fs::write(po_dir.join("fr.po"), FRENCH).unwrap();
+        fs::write(po_dir.join("pt_BR.po"), "msgid \"Hello\"\nmsgstr \"Olá\"\n").unwrap();
+        fs::write(po_dir.join("hello.pot"), "").unwrap();
+
+        let catalogs = Catalogs::new("hello")
+            .po_dir(&po_dir)
+            .out_dir(dir.0.join("locale"))
+            .compile()
+            .unwrap();
+
+        assert_eq!(
+            catalogs,
+            [
+                CompiledCatalog {
+                    language: "fr".to_owned(),
+                    path: dir.0.join("locale/fr/LC_MESSAGES/hello.mo"),
+                    translated: 1,
+                    fuzzy: 1,
+                    untranslated: 1,
+                    obsolete: 1,
+                },
+                CompiledCatalog {
+                    language: "pt_BR".to_owned(),
+                    path: dir.0.join("locale/pt_BR/LC_MESSAGES/hello.mo"),
+                    translated: 1,
+                    fuzzy: 0,
+                    untranslated: 0,
+                    obsolete: 0,
+                },
+            ]
+        );
+
+        let expected = po::parse(FRENCH)
+            .unwrap()
+            .into_iter()
+            .filter(|entry| mo::should_include(entry, false))
+            .collect::<Vec<_>>();
+        let mut contents = vec![];
+        mo::write(&mut contents, &expected).unwrap();
+        assert_eq!(fs::read(&catalogs[0].path).unwrap(), contents);
+    }
+
+    #[test]
+    fn languages_come_from_linguas() {
+        let dir = TempDir::new("linguas");
+        fs::write(dir.0.join("LINGUAS"), "# Languages\nfr\n").unwrap();
+        fs::write(dir.0.join("fr.po"), FRENCH).unwrap();
+        fs::write(dir.0.join("de.po"), "").unwrap();
+
+        let catalogs = Catalogs::new("hello")
+            .po_dir(&dir.0)
+            .out_dir(&dir.0)
+            .use_fuzzy(true)
+            .compile()
+            .unwrap();
+        assert_eq!(catalogs.len(), 1);
+        assert_eq!(catalogs[0].language, "fr");
+        assert_eq!(catalogs[0].translated, 2);
+
+        let catalogs = Catalogs::new("hello")
+            .po_dir(&dir.0)
+            .out_dir(&dir.0)
+            .languages(vec!["de"])
+            .compile()
+            .unwrap();
+        assert_eq!(catalogs[0].language, "de");
+        assert_eq!(catalogs[0].translated, 0);
+    }
+
+    #[test]
+    fn embedded_catalogs_are_listed() {
+        let dir = TempDir::new("embed");
+        for path in &[
+            "fr/LC_MESSAGES/hello.mo",
+            "de/LC_MESSAGES/hello.mo",
+            "de/LC_MESSAGES/hello.po",
+            "de/LC_TIME/hello.mo",
+        ] {
+            let path = dir.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(dir.0.join("README"), "").unwrap();
+
+        let catalogs = find_catalogs(&dir.0).unwrap();
+        assert_eq!(
+            catalogs,
+            [
+                "de/LC_MESSAGES/hello.mo",
+                "de/LC_TIME/hello.mo",
+                "fr/LC_MESSAGES/hello.mo"
+            ]
+        );
+
+        let index = embedded_catalogs_index(Path::new("/locale"), &catalogs[..1]).unwrap();
+        let expected_path = Path::new("/locale")
+            .join("de")
+            .join("LC_MESSAGES")
+            .join("hello.mo");
+        assert_eq!(
+            index,
+            format!(
+                r#"// Generated by gettext-tools.
+&[
+    EmbeddedCatalog {{
+        path: "de/LC_MESSAGES/hello.mo",
+        data: include_bytes!({:?}),
+    }},
+]
+"#,
+                expected_path.to_str().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn errors_point_to_files() {
+        let dir = TempDir::new("errors");
+        fs::write(dir.0.join("fr.po"), "msgid \"Hello\"\n").unwrap();
+
+        let error = Catalogs::new("hello")
+            .po_dir(&dir.0)
+            .out_dir(&dir.0)
+            .compile()
+            .unwrap_err();
+        match error {
+            BuildError::Parse(path, ParseError::MissingMsgstr(1)) => {
+                assert_eq!(path, dir.0.join("fr.po"))
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+
+        let error = Catalogs::new("hello")
+            .po_dir(&dir.0)
+            .out_dir(&dir.0)
+            .languages(vec!["de"])
+            .compile()
+            .unwrap_err();
+        match error {
+            BuildError::Io(path, _) => assert_eq!(path, dir.0.join("de.po")),
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,1199 @@
+//! Extraction of translatable strings from Rust sources.
+//!
+//! This is what `xgettext` does for C, but it understands Rust syntax: raw strings, escapes like
+//! `\u{1F600}`, lifetimes, nested block comments, and macro invocations. By default, it looks for
+//! the functions and macros of the gettext-rs crate (see [`DEFAULT_KEYWORDS`]), and for
+//! `// TRANSLATORS:` comments right before them.
+//!
+//! ```rust
+//! use gettext_tools::extract::Extractor;
+//!
+//! let mut extractor = Extractor::new();
+//! extractor.extract_str(
+//!     "src/main.rs",
+//!     r#"
+//!         // TRANSLATORS: shown on startup
+//!         println!("{}", gettext("Hello, world!"));
+//!         println!("{}", npgettext("mail", "One message", "Many messages", n));
+//!     "#,
+//! );
+//!
+//! let messages = extractor.messages();
+//! assert_eq!(messages[0].msgid, "Hello, world!");
+//! assert_eq!(messages[0].comments, ["TRANSLATORS: shown on startup"]);
+//! assert_eq!(messages[1].msgctxt.as_deref(), Some("mail"));
+//! assert_eq!(messages[1].msgid_plural.as_deref(), Some("Many messages"));
+//! ```
+//!
+//! Only calls whose arguments are string literals are extracted; `gettext(variable)` is skipped,
+//! just like with `xgettext`. Strings with 0 bytes are skipped too, as they can't be passed to
+//! gettext.
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Keywords that the [`Extractor`] looks for by default, in the `xgettext --keyword` syntax.
+///
+/// These cover the functions of the gettext-rs crate, including those in its `fallible` and
+/// `borrowed` modules, and its macros.
+pub const DEFAULT_KEYWORDS: &[&str] = &[
+    "gettext:1",
+    "dgettext:2",
+    "dcgettext:2",
+    "ngettext:1,2",
+    "dngettext:2,3",
+    "dcngettext:2,3",
+    "pgettext:1c,2",
+    "dpgettext:2c,3",
+    "dcpgettext:2c,3",
+    "npgettext:1c,2,3",
+    "dnpgettext:2c,3,4",
+    "dcnpgettext:2c,3,4",
+    "gettext_cstr:1",
+    "dgettext_cstr:2",
+    "dcgettext_cstr:2",
+    "ngettext_cstr:1,2",
+    "dngettext_cstr:2,3",
+    "gettext_fmt:1",
+    "ngettext_fmt:1,2",
+    "pgettext_fmt:1c,2",
+    "npgettext_fmt:1c,2,3",
+];
+
+/// The comment tag that the [`Extractor`] looks for by default.
+pub const DEFAULT_COMMENT_TAG: &str = "TRANSLATORS:";
+
+/// Errors that might come up while parsing a keyword specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKeyword(pub String);
+
+impl fmt::Display for InvalidKeyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"Keyword specification "{}" is invalid."#, self.0)
+    }
+}
+
+impl error::Error for InvalidKeyword {}
+
+/// A function or macro that marks strings for translation, and the positions of its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyword {
+    /// Name of the function or macro, without the `!`.
+    pub name: String,
+    /// 1-based position of the `msgid` argument.
+    pub msgid: usize,
+    /// 1-based position of the `msgid_plural` argument, if any.
+    pub msgid_plural: Option<usize>,
+    /// 1-based position of the `msgctxt` argument, if any.
+    pub msgctxt: Option<usize>,
+}
+
+impl Keyword {
+    /// Parses a keyword specification in the `xgettext --keyword` syntax: `name` (same as
+    /// `name:1`), `name:msgid`, `name:msgid,msgid_plural`, where either position may be followed
+    /// by another position with a `c` suffix, which points to `msgctxt`. For example,
+    /// `npgettext:1c,2,3`.
+    pub fn parse(spec: &str) -> Result<Keyword, InvalidKeyword> {
+        let invalid = || InvalidKeyword(spec.to_owned());
+
+        let mut parts = spec.splitn(2, ':');
+        let name = parts.next().unwrap_or("").trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+
+        let mut msgctxt = None;
+        let mut positions = vec![];
+        if let Some(arguments) = parts.next() {
+            for argument in arguments.split(',') {
+                let argument = argument.trim();
+                if let Some(position) = argument.strip_suffix('c') {
+                    if msgctxt.is_some() {
+                        return Err(invalid());
+                    }
+                    msgctxt = Some(parse_position(position).ok_or_else(invalid)?);
+                } else {
+                    positions.push(parse_position(argument).ok_or_else(invalid)?);
+                }
+            }
+        }
+
+        let (msgid, msgid_plural) = match positions[..] {
+            [] => (1, None),
+            [msgid] => (msgid, None),
+            [msgid, msgid_plural] => (msgid, Some(msgid_plural)),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Keyword {
+            name: name.to_owned(),
+            msgid,
+            msgid_plural,
+            msgctxt,
+        })
+    }
+}
+
+fn parse_position(position: &str) -> Option<usize> {
+    position.parse().ok().filter(|&position| position > 0)
+}
+
+/// A message found in the sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub msgctxt: Option<String>,
+    pub msgid: String,
+    pub msgid_plural: Option<String>,
+    /// Files and lines where the message was found.
+    pub references: Vec<(String, usize)>,
+    /// Comments for translators, one element per line.
+    pub comments: Vec<String>,
+}
+
+/// Information about the package that goes into the header of the POT file.
+#[derive(Debug, Clone, Default)]
+pub struct PotHeader {
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub msgid_bugs_address: Option<String>,
+    pub copyright_holder: Option<String>,
+    /// Value of `POT-Creation-Date`. Current time is used if this is `None`.
+    pub creation_date: Option<String>,
+}
+
+/// Collects messages from Rust sources.
+pub struct Extractor {
+    keywords: HashMap<String, Keyword>,
+    comment_tag: Option<String>,
+    messages: Vec<Message>,
+    index: HashMap<(Option<String>, String), usize>,
+}
+
+impl Default for Extractor {
+    fn default() -> Extractor {
+        Extractor::new()
+    }
+}
+
+impl Extractor {
+    /// Creates an extractor that looks for [`DEFAULT_KEYWORDS`] and [`DEFAULT_COMMENT_TAG`].
+    pub fn new() -> Extractor {
+        let extractor = Extractor {
+            keywords: HashMap::new(),
+            comment_tag: Some(DEFAULT_COMMENT_TAG.to_owned()),
+            messages: vec![],
+            index: HashMap::new(),
+        };
+        DEFAULT_KEYWORDS.iter().fold(extractor, |extractor, spec| {
+            extractor.keyword(Keyword::parse(spec).expect("default keywords are valid"))
+        })
+    }
+
+    /// Looks for the given keyword as well. If there is already a keyword with the same name, it
+    /// is replaced.
+    pub fn keyword(mut self, keyword: Keyword) -> Self {
+        self.keywords.insert(keyword.name.clone(), keyword);
+        self
+    }
+
+    /// Forgets all keywords, including the default ones.
+    pub fn no_keywords(mut self) -> Self {
+        self.keywords.clear();
+        self
+    }
+
+    /// Sets the tag that marks comments for translators, or disables such comments if `None`.
+    ///
+    /// Comments are extracted if they precede the line with the keyword, or if they're on the
+    /// same line before the keyword. Lines of a comment before the one containing the tag are
+    /// skipped.
+    pub fn comment_tag<T: Into<String>>(mut self, tag: Option<T>) -> Self {
+        self.comment_tag = tag.map(Into::into);
+        self
+    }
+
+    /// Returns the messages found so far, in the order in which they were first encountered.
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Extracts messages from a Rust source. `path` is only used for references.
+    pub fn extract_str(&mut self, path: &str, source: &str) {
+        let tokens = tokenize(source);
+        for (position, token) in tokens.iter().enumerate() {
+            let keyword = match token.kind {
+                TokenKind::Ident(ref name) => match self.keywords.get(name) {
+                    Some(keyword) => keyword,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if position > 0 && tokens[position - 1].kind == TokenKind::Ident("fn".to_owned()) {
+                continue;
+            }
+
+            let arguments = match arguments(&tokens[position + 1..]) {
+                Some(arguments) => arguments,
+                None => continue,
+            };
+            let argument = |position: usize| -> Option<Option<String>> {
+                match arguments.get(position - 1) {
+                    Some(Some(string)) => Some(Some(string.clone())),
+                    _ => None,
+                }
+            };
+            let optional = |position: Option<usize>| match position {
+                Some(position) => argument(position),
+                None => Some(None),
+            };
+
+            let (msgid, msgid_plural, msgctxt) = match (
+                argument(keyword.msgid),
+                optional(keyword.msgid_plural),
+                optional(keyword.msgctxt),
+            ) {
+                (Some(Some(msgid)), Some(msgid_plural), Some(msgctxt)) => {
+                    (msgid, msgid_plural, msgctxt)
+                }
+                _ => continue,
+            };
+            // The empty msgid is reserved for the header, and 0 bytes can't be passed to gettext.
+            let has_nul =
+                |string: &Option<String>| string.as_ref().is_some_and(|s| s.contains('\0'));
+            if msgid.is_empty()
+                || msgid.contains('\0')
+                || has_nul(&msgid_plural)
+                || has_nul(&msgctxt)
+            {
+                continue;
+            }
+
+            let comments = match self.comment_tag {
+                Some(ref tag) => tagged_comments(&token.comments, tag),
+                None => vec![],
+            };
+            self.add(Message {
+                msgctxt,
+                msgid,
+                msgid_plural,
+                references: vec![(path.to_owned(), token.line)],
+                comments,
+            });
+        }
+    }
+
+    /// Extracts messages from a Rust source file.
+    pub fn extract_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        self.extract_str(&reference_path(path), &source);
+        Ok(())
+    }
+
+    /// Extracts messages from all `.rs` files in the directory and its subdirectories, in
+    /// alphabetical order. Hidden directories and directories named `target` are skipped.
+    pub fn extract_dir<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for entry in entries {
+            let name = entry
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if entry.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    self.extract_dir(&entry)?;
+                }
+            } else if name.ends_with(".rs") {
+                self.extract_file(&entry)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add(&mut self, message: Message) {
+        let key = (message.msgctxt.clone(), message.msgid.clone());
+        match self.index.get(&key) {
+            Some(&index) => {
+                let existing = &mut self.messages[index];
+                if existing.msgid_plural.is_none() {
+                    existing.msgid_plural = message.msgid_plural;
+                }
+                existing.references.extend(message.references);
+                for comment in message.comments {
+                    if !existing.comments.contains(&comment) {
+                        existing.comments.push(comment);
+                    }
+                }
+            }
+            None => {
+                self.index.insert(key, self.messages.len());
+                self.messages.push(message);
+            }
+        }
+    }
+
+    /// Writes the messages found so far as a POT file.
+    pub fn write_pot<W: Write>(&self, mut out: W, header: &PotHeader) -> io::Result<()> {
+        let creation_date = match header.creation_date {
+            Some(ref date) => date.clone(),
+            None => current_date(),
+        };
+        writeln!(out, "# SOME DESCRIPTIVE TITLE.")?;
+        writeln!(
+            out,
+            "# Copyright (C) YEAR {}",
+            header
+                .copyright_holder
+                .as_deref()
+                .unwrap_or("THE PACKAGE'S COPYRIGHT HOLDER")
+        )?;
+        writeln!(
+            out,
+            "# This file is distributed under the same license as the {} package.",
+            header.package_name.as_deref().unwrap_or("PACKAGE")
+        )?;
+        writeln!(out, "# FIRST AUTHOR <EMAIL@ADDRESS>, YEAR.")?;
+        writeln!(out, "#")?;
+        writeln!(out, "#, fuzzy")?;
+
+        let mut fields = vec![
+            format!(
+                "Project-Id-Version: {} {}",
+                header.package_name.as_deref().unwrap_or("PACKAGE"),
+                header.package_version.as_deref().unwrap_or("VERSION")
+            ),
+            format!(
+                "Report-Msgid-Bugs-To: {}",
+                header.msgid_bugs_address.as_deref().unwrap_or("")
+            ),
+            format!("POT-Creation-Date: {}", creation_date),
+            "PO-Revision-Date: YEAR-MO-DA HO:MI+ZONE".to_owned(),
+            "Last-Translator: FULL NAME <EMAIL@ADDRESS>".to_owned(),
+            "Language-Team: LANGUAGE <LL@li.org>".to_owned(),
+            "Language: ".to_owned(),
+            "MIME-Version: 1.0".to_owned(),
+            "Content-Type: text/plain; charset=UTF-8".to_owned(),
+            "Content-Transfer-Encoding: 8bit".to_owned(),
+        ];
+        if self
+            .messages
+            .iter()
+            .any(|message| message.msgid_plural.is_some())
+        {
+            fields.push("Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;".to_owned());
+        }
+        writeln!(out, "msgid \"\"")?;
+        writeln!(out, "msgstr \"\"")?;
+        for field in fields {
+            writeln!(out, "\"{}\\n\"", escape(&field))?;
+        }
+
+        for message in &self.messages {
+            writeln!(out)?;
+            for comment in &message.comments {
+                writeln!(out, "#. {}", comment)?;
+            }
+            write_references(&mut out, &message.references)?;
+            if let Some(ref msgctxt) = message.msgctxt {
+                write_string(&mut out, "msgctxt", msgctxt)?;
+            }
+            write_string(&mut out, "msgid", &message.msgid)?;
+            match message.msgid_plural {
+                Some(ref msgid_plural) => {
+                    write_string(&mut out, "msgid_plural", msgid_plural)?;
+                    writeln!(out, "msgstr[0] \"\"")?;
+                    writeln!(out, "msgstr[1] \"\"")?;
+                }
+                None => writeln!(out, "msgstr \"\"")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the path with `/` as a separator, like GNU tools write it.
+fn reference_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+        .replace("//", "/")
+}
+
+/// Returns the lines of `comments` starting from the one that begins with `tag`.
+fn tagged_comments(comments: &[String], tag: &str) -> Vec<String> {
+    match comments.iter().position(|line| line.starts_with(tag)) {
+        Some(start) => comments[start..].to_vec(),
+        None => vec![],
+    }
+}
+
+fn write_references<W: Write>(out: &mut W, references: &[(String, usize)]) -> io::Result<()> {
+    const WIDTH: usize = 79;
+
+    let mut line = String::new();
+    for (path, number) in references {
+        let reference = format!("{}:{}", path, number);
+        if !line.is_empty() && line.len() + 1 + reference.len() > WIDTH {
+            writeln!(out, "{}", line)?;
+            line.clear();
+        }
+        if line.is_empty() {
+            line.push_str("#:");
+        }
+        line.push(' ');
+        line.push_str(&reference);
+    }
+    if !line.is_empty() {
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\x07' => escaped.push_str("\\a"),
+            '\x08' => escaped.push_str("\\b"),
+            '\x0b' => escaped.push_str("\\v"),
+            '\x0c' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 || c == '\x7f' => {
+                escaped.push_str(&format!("\\{:03o}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes `keyword "string"`, splitting the string after each newline like `xgettext` does.
+fn write_string<W: Write>(out: &mut W, keyword: &str, string: &str) -> io::Result<()> {
+    let lines: Vec<&str> = string.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        return writeln!(out, "{} \"{}\"", keyword, escape(string));
+    }
+
+    writeln!(out, "{} \"\"", keyword)?;
+    for line in lines {
+        writeln!(out, "\"{}\"", escape(line))?;
+    }
+    Ok(())
+}
+
+/// Returns the current UTC time in the format used by PO headers.
+fn current_date() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    format_date(seconds)
+}
+
+fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Converts days since the epoch into a date in the proleptic Gregorian calendar. See
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}+0000",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60
+    )
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    /// A string literal, with escapes already processed.
+    Str(String),
+    Punct(char),
+    /// Numbers, byte strings, characters, and lifetimes.
+    Other,
+}
+
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    /// Comments that precede the first token on this token's line, one element per line.
+    comments: Vec<String>,
+}
+
+/// Splits the argument list that starts at `tokens[0]` (which must be an opening bracket,
+/// possibly preceded by the `!` of a macro invocation) at top-level commas. Returns the value of
+/// each argument if it's a single string literal, and `None` if it's something else.
+fn arguments(tokens: &[Token]) -> Option<Vec<Option<String>>> {
+    let tokens = match tokens.first() {
+        Some(Token {
+            kind: TokenKind::Punct('!'),
+            ..
+        }) => &tokens[1..],
+        _ => tokens,
+    };
+    match tokens.first() {
+        Some(Token {
+            kind: TokenKind::Punct('('),
+            ..
+        })
+        | Some(Token {
+            kind: TokenKind::Punct('['),
+            ..
+        })
+        | Some(Token {
+            kind: TokenKind::Punct('{'),
+            ..
+        }) => {}
+        _ => return None,
+    }
+
+    let mut result = vec![];
+    let mut current: Vec<&TokenKind> = vec![];
+    let mut depth = 0;
+    for token in tokens {
+        match token.kind {
+            TokenKind::Punct('(') | TokenKind::Punct('[') | TokenKind::Punct('{') => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            TokenKind::Punct(')') | TokenKind::Punct(']') | TokenKind::Punct('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    if !current.is_empty() {
+                        result.push(literal(&current));
+                    }
+                    return Some(result);
+                }
+            }
+            TokenKind::Punct(',') if depth == 1 => {
+                result.push(literal(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(&token.kind);
+    }
+
+    None
+}
+
+fn literal(tokens: &[&TokenKind]) -> Option<String> {
+    match tokens {
+        [TokenKind::Str(string)] => Some(string.clone()),
+        _ => None,
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+    let mut tokens = vec![];
+    // Comments that haven't been followed by a token yet.
+    let mut pending: Vec<String> = vec![];
+    // The line of the first token after some comments, and those comments.
+    let mut commented_line = (0, vec![]);
+
+    while let Some(item) = lexer.next_item() {
+        match item {
+            Item::Comment(lines) => pending.extend(lines),
+            Item::Token(kind, line) => {
+                if !pending.is_empty() {
+                    commented_line = (line, pending.split_off(0));
+                }
+                let comments = if commented_line.0 == line {
+                    commented_line.1.clone()
+                } else {
+                    vec![]
+                };
+                tokens.push(Token {
+                    kind,
+                    line,
+                    comments,
+                });
+            }
+        }
+    }
+
+    tokens
+}
+
+enum Item {
+    /// Lines of a comment, with comment markers and surrounding whitespace removed.
+    Comment(Vec<String>),
+    /// A token and the line it starts on.
+    Token(TokenKind, usize),
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).cloned()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn next_item(&mut self) -> Option<Item> {
+        while self.peek(0)?.is_whitespace() {
+            self.bump();
+        }
+
+        let line = self.line;
+        let c = self.peek(0)?;
+        let kind = match c {
+            '/' if self.peek(1) == Some('/') => return Some(self.line_comment()),
+            '/' if self.peek(1) == Some('*') => return Some(self.block_comment()),
+            '"' => {
+                self.bump();
+                TokenKind::Str(self.string())
+            }
+            'r' if self.raw_string_ahead(1) => {
+                self.bump();
+                TokenKind::Str(self.raw_string())
+            }
+            'b' | 'c' if self.peek(1) == Some('"') => {
+                self.bump();
+                self.bump();
+                self.string();
+                TokenKind::Other
+            }
+            'b' | 'c' if self.peek(1) == Some('r') && self.raw_string_ahead(2) => {
+                self.bump();
+                self.bump();
+                self.raw_string();
+                TokenKind::Other
+            }
+            'b' if self.peek(1) == Some('\'') => {
+                self.bump();
+                self.bump();
+                self.char_literal();
+                TokenKind::Other
+            }
+            '\'' => {
+                self.bump();
+                if self.peek(0) == Some('\\') || self.peek(1) == Some('\'') {
+                    self.char_literal();
+                } else {
+                    // A lifetime or a label.
+                    while self.peek(0).is_some_and(is_ident_char) {
+                        self.bump();
+                    }
+                }
+                TokenKind::Other
+            }
+            'r' if self.peek(1) == Some('#') && self.peek(2).is_some_and(is_ident_start) => {
+                self.bump();
+                self.bump();
+                TokenKind::Ident(self.ident())
+            }
+            c if is_ident_start(c) => TokenKind::Ident(self.ident()),
+            c if c.is_ascii_digit() => {
+                while self.peek(0).is_some_and(is_ident_char) {
+                    self.bump();
+                }
+                TokenKind::Other
+            }
+            c => {
+                self.bump();
+                TokenKind::Punct(c)
+            }
+        };
+
+        Some(Item::Token(kind, line))
+    }
+
+    fn line_comment(&mut self) -> Item {
+        let mut text = String::new();
+        while let Some(c) = self.peek(0) {
+            if c == '\n' {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+
+        let text = text.trim_start_matches('/').trim_start_matches('!').trim();
+        Item::Comment(vec![text.to_owned()])
+    }
+
+    fn block_comment(&mut self) -> Item {
+        self.bump();
+        self.bump();
+        let mut text = String::new();
+        let mut depth = 1;
+        while let Some(c) = self.bump() {
+            if c == '/' && self.peek(0) == Some('*') {
+                self.bump();
+                depth += 1;
+                text.push_str("/*");
+            } else if c == '*' && self.peek(0) == Some('/') {
+                self.bump();
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                text.push_str("*/");
+            } else {
+                text.push(c);
+            }
+        }
+
+        let lines = text
+            .trim_start_matches(['*', '!'])
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                match line.strip_prefix('*') {
+                    Some(rest) => rest.trim().to_owned(),
+                    None => line.to_owned(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let start = lines.iter().position(|line| !line.is_empty());
+        let end = lines.iter().rposition(|line| !line.is_empty());
+        match (start, end) {
+            (Some(start), Some(end)) => Item::Comment(lines[start..=end].to_vec()),
+            _ => Item::Comment(vec![]),
+        }
+    }
+
+    /// Checks if a raw string starts at `offset`, i.e. there's a `"` after some `#`s.
+    fn raw_string_ahead(&self, mut offset: usize) -> bool {
+        while self.peek(offset) == Some('#') {
+            offset += 1;
+        }
+        self.peek(offset) == Some('"')
+    }
+
+    /// Reads a raw string, starting with the `#`s before the opening quote.
+    fn raw_string(&mut self) -> String {
+        let mut hashes = 0;
+        while self.peek(0) == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        self.bump();
+
+        let mut string = String::new();
+        while let Some(c) = self.bump() {
+            if c == '"' && (0..hashes).all(|offset| self.peek(offset) == Some('#')) {
+                for _ in 0..hashes {
+                    self.bump();
+                }
+                break;
+            }
+            string.push(c);
+        }
+        string.replace("\r\n", "\n")
+    }
+
+    /// Reads a string after the opening quote, processing escapes.
+    fn string(&mut self) -> String {
+        let mut string = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(c) = self.escape() {
+                        string.push(c);
+                    }
+                }
+                '\r' if self.peek(0) == Some('\n') => {}
+                c => string.push(c),
+            }
+        }
+        string
+    }
+
+    fn char_literal(&mut self) {
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '\'' => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Processes an escape after the backslash. Returns `None` for line continuations.
+    fn escape(&mut self) -> Option<char> {
+        let c = self.bump()?;
+        Some(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'x' => {
+                let digits: String = (0..2).filter_map(|_| self.bump()).collect();
+                u8::from_str_radix(&digits, 16).map_or('\u{fffd}', char::from)
+            }
+            'u' => {
+                let mut digits = String::new();
+                if self.peek(0) == Some('{') {
+                    self.bump();
+                    while let Some(c) = self.bump() {
+                        if c == '}' {
+                            break;
+                        }
+                        if c != '_' {
+                            digits.push(c);
+                        }
+                    }
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .unwrap_or('\u{fffd}')
+            }
+            '\r' | '\n' => {
+                while self.peek(0).is_some_and(char::is_whitespace) {
+                    self.bump();
+                }
+                return None;
+            }
+            c => c,
+        })
+    }
+
+    fn ident(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek(0) {
+            if !is_ident_char(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump();
+        }
+        ident
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(source: &str) -> Vec<Message> {
+        let mut extractor = Extractor::new();
+        extractor.extract_str("src/lib.rs", source);
+        extractor.messages().to_vec()
+    }
+
+    fn message(msgctxt: Option<&str>, msgid: &str, msgid_plural: Option<&str>) -> Message {
+        Message {
+            msgctxt: msgctxt.map(String::from),
+            msgid: msgid.to_owned(),
+            msgid_plural: msgid_plural.map(String::from),
+            references: vec![],
+            comments: vec![],
+        }
+    }
+
+    fn without_references(messages: Vec<Message>) -> Vec<Message> {
+        messages
+            .into_iter()
+            .map(|message| Message {
+                references: vec![],
+                ..message
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keywords_are_parsed() {
+        assert_eq!(
+            Keyword::parse("tr"),
+            Ok(Keyword {
+                name: "tr".to_owned(),
+                msgid: 1,
+                msgid_plural: None,
+                msgctxt: None,
+            })
+        );
+        assert_eq!(
+            Keyword::parse("dcnpgettext:2c,3,4"),
+            Ok(Keyword {
+                name: "dcnpgettext".to_owned(),
+                msgid: 3,
+                msgid_plural: Some(4),
+                msgctxt: Some(2),
+            })
+        );
+        assert!(Keyword::parse("").is_err());
+        assert!(Keyword::parse("tr:0").is_err());
+        assert!(Keyword::parse("tr:1,2,3").is_err());
+        assert!(Keyword::parse("tr:1c,2c").is_err());
+        assert!(Keyword::parse("tr!:1").is_err());
+    }
+
+    #[test]
+    fn functions_and_macros_are_recognized() {
+        let source = r#"
+            use gettextrs::*;
+
+            fn main() {
+                gettext("Hello");
+                gettextrs::dgettext("domain", "In a domain");
+                ngettext("One file", "{} files", n);
+                fallible::pgettext("menu", "Open")?;
+                npgettext("mail", "One message", "Many messages", 2);
+                dcnpgettext("domain", "ctx", "One", "Many", 2, LocaleCategory::LcMessages);
+                gettext!("From a macro");
+                ngettext_fmt!["{n} apple", "{n} apples", n, n = n];
+                pgettext_fmt!{"ctx", "Braces"};
+                domain.gettext("A method");
+            }
+        "#;
+
+        assert_eq!(
+            without_references(extract(source)),
+            [
+                message(None, "Hello", None),
+                message(None, "In a domain", None),
+                message(None, "One file", Some("{} files")),
+                message(Some("menu"), "Open", None),
+                message(Some("mail"), "One message", Some("Many messages")),
+                message(Some("ctx"), "One", Some("Many")),
+                message(None, "From a macro", None),
+                message(None, "{n} apple", Some("{n} apples")),
+                message(Some("ctx"), "Braces", None),
+                message(None, "A method", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_literal_arguments_are_skipped() {
+        let source = r#"
+            pub fn gettext<T: Into<String>>(msgid: T) -> String { unimplemented!() }
+            gettext(msgid);
+            gettext(format!("{}", 1));
+            gettext("a" );
+            ngettext("b", plural, 2);
+            pgettext(CONTEXT, "c");
+            gettext("");
+            let gettext = 1;
+        "#;
+
+        assert_eq!(
+            without_references(extract(source)),
+            [message(None, "a", None)]
+        );
+    }
+
+    #[test]
+    fn literals_are_unescaped() {
+        let source = r###"
+            gettext("Quote \" backslash \\ tab \t newline \n");
+            gettext("Skipped \0 nul");
+            gettext("Unicode \u{1F600} \x41");
+            gettext("Continued \
+                     line");
+            gettext(r"Raw \n");
+            gettext(r#"Raw "quoted""#);
+            gettext(r##"Raw "# hash"##);
+            let c = '"'; let l: &'static str = "x"; let b = b"gettext(\"no\")";
+            gettext("After a char");
+            /* gettext("In /* nested */ a comment") */
+            gettext(r#gettext);
+        "###;
+
+        let msgids: Vec<String> = extract(source)
+            .into_iter()
+            .map(|message| message.msgid)
+            .collect();
+        assert_eq!(
+            msgids,
+            [
+                "Quote \" backslash \\ tab \t newline \n",
+                "Unicode 😀 A",
+                "Continued line",
+                "Raw \\n",
+                "Raw \"quoted\"",
+                "Raw \"# hash",
+                "After a char",
+            ]
+        );
+    }
+
+    #[test]
+    fn references_and_comments_are_collected() {
+        let source = r#"
+            // TRANSLATORS: Greeting shown on startup.
+            // It's informal.
+            let greeting = gettext("Hello");
+
+            // Not for translators.
+            let greeting = gettext("Hello");
+
+            // This line is skipped.
+            // TRANSLATORS: This one isn't.
+            println!("{}", gettext("Bye"));
+
+            // TRANSLATORS: too far away
+            let x = 1;
+            gettext("Far");
+
+            /* TRANSLATORS: A block comment
+             * over two lines.
+             */
+            gettext!("Block");
+        "#;
+
+        let messages = extract(source);
+        assert_eq!(messages.len(), 4);
+
+        assert_eq!(
+            messages[0].references,
+            [("src/lib.rs".to_owned(), 4), ("src/lib.rs".to_owned(), 7)]
+        );
+        assert_eq!(
+            messages[0].comments,
+            ["TRANSLATORS: Greeting shown on startup.", "It's informal."]
+        );
+        assert_eq!(messages[1].comments, ["TRANSLATORS: This one isn't."]);
+        assert!(messages[2].comments.is_empty());
+        assert_eq!(
+            messages[3].comments,
+            ["TRANSLATORS: A block comment", "over two lines."]
+        );
+    }
+
+    #[test]
+    fn pot_is_written() {
+        let mut extractor = Extractor::new();
+        extractor.extract_str(
+            "src/main.rs",
+            r#"
+                // TRANSLATORS: a "quoted" word
+                gettext("Say \"hi\"");
+                npgettext("ctx", "One line\n", "Lines:\nfirst\nsecond", n);
+            "#,
+        );
+
+        let header = PotHeader {
+            package_name: Some("hello".to_owned()),
+            package_version: Some("1.0".to_owned()),
+            creation_date: Some("2024-01-02 03:04+0000".to_owned()),
+            ..PotHeader::default()
+        };
+        let mut pot = vec![];
+        extractor.write_pot(&mut pot, &header).unwrap();
+
+        let expected = r#"# SOME DESCRIPTIVE TITLE.
+# Copyright (C) YEAR THE PACKAGE'S COPYRIGHT HOLDER
+# This file is distributed under the same license as the hello package.
+# FIRST AUTHOR <EMAIL@ADDRESS>, YEAR.
+#
+#, fuzzy
+msgid ""
+msgstr ""
+"Project-Id-Version: hello 1.0\n"
+"Report-Msgid-Bugs-To: \n"
+"POT-Creation-Date: 2024-01-02 03:04+0000\n"
+"PO-Revision-Date: YEAR-MO-DA HO:MI+ZONE\n"
+"Last-Translator: FULL NAME <EMAIL@ADDRESS>\n"
+"Language-Team: LANGUAGE <LL@li.org>\n"
+"Language: \n"
+"MIME-Version: 1.0\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Content-Transfer-Encoding: 8bit\n"
+"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n"
+
+#. TRANSLATORS: a "quoted" word
+#: src/main.rs:3
+msgid "Say \"hi\""
+msgstr ""
+
+#: src/main.rs:4
+msgctxt "ctx"
+msgid "One line\n"
+msgid_plural ""
+"Lines:\n"
+"first\n"
+"second"
+msgstr[0] ""
+msgstr[1] ""
+"#;
+        assert_eq!(String::from_utf8(pot).unwrap(), expected);
+    }
+
+    #[test]
+    fn long_reference_lists_are_wrapped() {
+        let references: Vec<(String, usize)> = (1..=10)
+            .map(|line| ("src/some/long/path.rs".to_owned(), line * 100))
+            .collect();
+        let mut out = vec![];
+        write_references(&mut out, &references).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.lines().count() > 1);
+        assert!(out
+            .lines()
+            .all(|line| line.len() <= 79 && line.starts_with("#: ")));
+        assert_eq!(out.matches("src/some/long/path.rs").count(), 10);
+    }
+
+    #[test]
+    fn dates_are_formatted() {
+        assert_eq!(format_date(0), "1970-01-01 00:00+0000");
+        assert_eq!(format_date(951_782_400 + 3_723), "2000-02-29 01:02+0000");
+        assert_eq!(format_date(1_704_164_640), "2024-01-02 03:04+0000");
+    }
+}
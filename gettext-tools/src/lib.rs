@@ -0,0 +1,16 @@
+//! # Tools for working with gettext catalogs in Rust projects.
+//!
+//! GNU gettext comes with a set of tools to extract messages from sources, and to compile
+//! translations. They don't know much about Rust, though, and aren't always installed. This crate
+//! implements the parts that Rust projects need in pure Rust:
+//!
+//! - [`extract`] finds calls to gettext-rs functions and macros in Rust sources, and writes them
+//!   into a POT file. It's also available as the `xgettext-rs` binary.
+//! - [`build`] compiles PO files into MO files from build scripts, so that the translations can be
+//!   shipped with the crate without requiring `msgfmt` on the build machine. It's built on the
+//!   [`po`] parser and the [`mo`] writer.
+
+pub mod build;
+pub mod extract;
+pub mod mo;
+pub mod po;
@@ -0,0 +1,304 @@
+//! Writing of MO files, the binary catalogs that gettext loads at runtime.
+//!
+//! The files are laid out like `msgfmt` does it: the messages are sorted, and a hash table is
+//! included to speed up lookups. The byte order is little-endian.
+//!
+//! ```rust
+//! use gettext_tools::{mo, po};
+//!
+//! let entries = po::parse("msgid \"Hello\"\nmsgstr \"Bonjour\"\n").unwrap();
+//! let mut catalog = vec![];
+//! mo::write(&mut catalog, &entries).unwrap();
+//! assert_eq!(&catalog[..4], &[0xde, 0x12, 0x04, 0x95]);
+//! ```
+
+use std::io::{self, Write};
+
+use po::Entry;
+
+/// The magic number that starts every MO file. It's written in little-endian byte order.
+const MAGIC: u32 = 0x9504_12de;
+
+/// Size of the header, in bytes.
+const HEADER_SIZE: u32 = 28;
+
+/// Writes the given entries into an MO file.
+///
+/// All entries are written, so the caller should filter out those that shouldn't end up in the
+/// catalog, like fuzzy or untranslated ones (see [`should_include`]). Entries are expected to be
+/// unique, which [`po::parse`][::po::parse] ensures.
+pub fn write<W: Write>(mut out: W, entries: &[Entry]) -> io::Result<()> {
+    let mut messages: Vec<(Vec<u8>, Vec<u8>)> = entries
+        .iter()
+        .map(|entry| (key(entry), entry.msgstr.join("\0").into_bytes()))
+        .collect();
+    messages.sort();
+
+    let count = messages.len() as u32;
+    let hash_table_size = hash_table_size(count);
+    let originals_offset = HEADER_SIZE;
+    let translations_offset = originals_offset + 8 * count;
+    let hash_table_offset = translations_offset + 8 * count;
+    let strings_offset = hash_table_offset + 4 * hash_table_size;
+
+    let mut table = Vec::with_capacity(16 * messages.len());
+    let mut offset = strings_offset;
+    for string in messages
+        .iter()
+        .map(|(key, _)| key)
+        .chain(messages.iter().map(|(_, value)| value))
+    {
+        table.extend_from_slice(&(string.len() as u32).to_le_bytes());
+        table.extend_from_slice(&offset.to_le_bytes());
+        offset += string.len() as u32 + 1;
+    }
+
+    let mut hash_table = vec![0u32; hash_table_size as usize];
+    for (index, (key, _)) in messages.iter().enumerate() {
+        let hash = hash(key);
+        let mut position = hash % hash_table_size;
+        let increment = 1 + hash % (hash_table_size - 2);
+        while hash_table[position as usize] != 0 {
+            position = (position + increment) % hash_table_size;
+        }
+        hash_table[position as usize] = index as u32 + 1;
+    }
+
+    for value in &[
+        MAGIC,
+        0,
+        count,
+        originals_offset,
+        translations_offset,
+        hash_table_size,
+        hash_table_offset,
+    ] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.write_all(&table)?;
+    for value in hash_table {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    for (key, _) in &messages {
+        out.write_all(key)?;
+        out.write_all(b"\0")?;
+    }
+    for (_, value) in &messages {
+        out.write_all(value)?;
+        out.write_all(b"\0")?;
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if `msgfmt` would put the entry into the MO file: it's not obsolete, it's
+/// translated, and it's not fuzzy (unless `use_fuzzy` is `true`, like with `msgfmt --use-fuzzy`).
+/// The header is included even if it's fuzzy, as `msgfmt` does.
+pub fn should_include(entry: &Entry, use_fuzzy: bool) -> bool {
+    !entry.obsolete
+        && entry.is_translated()
+        && (use_fuzzy || entry.is_header() || !entry.is_fuzzy())
+}
+
+/// The string that gettext looks up: `msgid`, prefixed with `msgctxt` and `\x04` if there's a
+/// context, and followed by `\0` and `msgid_plural` if there's a plural.
+fn key(entry: &Entry) -> Vec<u8> {
+    let mut key = vec![];
+    if let Some(ref msgctxt) = entry.msgctxt {
+        key.extend_from_slice(msgctxt.as_bytes());
+        key.push(b'\x04');
+    }
+    key.extend_from_slice(entry.msgid.as_bytes());
+    if let Some(ref msgid_plural) = entry.msgid_plural {
+        key.push(b'\0');
+        key.extend_from_slice(msgid_plural.as_bytes());
+    }
+    key
+}
+
+/// The hash function from GNU gettext (hashpjw). Only the part of the key before the first 0 byte
+/// is hashed, i.e. `msgid_plural` doesn't count.
+fn hash(key: &[u8]) -> u32 {
+    let mut hash: u32 = 0;
+    for &byte in key.iter().take_while(|&&byte| byte != 0) {
+        hash = (hash << 4).wrapping_add(u32::from(byte));
+        let high = hash & 0xf000_0000;
+        if high != 0 {
+            hash ^= high >> 24;
+            hash ^= high;
+        }
+    }
+    hash
+}
+
+/// The smallest prime that is at least 4/3 of the number of messages, like older versions of
+/// `msgfmt` used. Newer ones search for a size with fewer collisions, but that's just an
+/// optimization.
+fn hash_table_size(count: u32) -> u32 {
+    let mut size = (count * 4 / 3).max(3) | 1;
+    while !is_prime(size) {
+        size += 2;
+    }
+    size
+}
+
+fn is_prime(n: u32) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use po;
+
+    fn read_u32(catalog: &[u8], offset: u32) -> u32 {
+        let offset = offset as usize;
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&catalog[offset..offset + 4]);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn read_string(catalog: &[u8], table_offset: u32, index: u32) -> &[u8] {
+        let length = read_u32(catalog, table_offset + 8 * index) as usize;
+        let offset = read_u32(catalog, table_offset + 8 * index + 4) as usize;
+        assert_eq!(catalog[offset + length], 0);
+        &catalog[offset..offset + length]
+    }
+
+    /// Looks the key up through the hash table, like gettext does.
+    fn lookup<'a>(catalog: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+        let size = read_u32(catalog, 20);
+        let hash_table_offset = read_u32(catalog, 24);
+        let hash = hash(key);
+        let mut position = hash % size;
+        let increment = 1 + hash % (size - 2);
+        loop {
+            let index = read_u32(catalog, hash_table_offset + 4 * position);
+            if index == 0 {
+                return None;
+            }
+            let original = read_string(catalog, read_u32(catalog, 12), index - 1);
+            if original.split(|&byte| byte == 0).next() == Some(key) {
+                return Some(read_string(catalog, read_u32(catalog, 16), index - 1));
+            }
+            position = (position + increment) % size;
+        }
+    }
+
+    #[test]
+    fn catalogs_are_written() {
+        let mut source = String::from("msgid \"\"\nmsgstr \"Language: fr\\n\"\n\n");
+        source.push_str("msgctxt \"mail\"\nmsgid \"message\"\nmsgstr \"courriel\"\n\n");
+        source.push_str(
+            "msgid \"apple\"\nmsgid_plural \"apples\"\nmsgstr[0] \"pomme\"\nmsgstr[1] \"pommes\"\n\n",
+        );
+        for i in 0..100 {
+            source.push_str(&format!("msgid \"m{}\"\nmsgstr \"t{}\"\n\n", i, i));
+        }
+        let entries = po::parse(&source).unwrap();
+
+        let mut catalog = vec![];
+        write(&mut catalog, &entries).unwrap();
+
+        assert_eq!(read_u32(&catalog, 0), MAGIC);
+        assert_eq!(read_u32(&catalog, 4), 0);
+        assert_eq!(read_u32(&catalog, 8), 103);
+        assert_eq!(read_u32(&catalog, 20), 137);
+
+        let originals = (0..103)
+            .map(|index| read_string(&catalog, read_u32(&catalog, 12), index))
+            .collect::<Vec<_>>();
+        let mut sorted = originals.clone();
+        sorted.sort();
+        assert_eq!(originals, sorted);
+        assert_eq!(originals[0], b"");
+        assert_eq!(originals[1], b"apple\0apples");
+
+        assert_eq!(lookup(&catalog, b""), Some(&b"Language: fr\n"[..]));
+        assert_eq!(lookup(&catalog, b"mail\x04message"), Some(&b"courriel"[..]));
+        assert_eq!(lookup(&catalog, b"apple"), Some(&b"pomme\0pommes"[..]));
+        assert_eq!(lookup(&catalog, b"message"), None);
+        for i in 0..100 {
+            let key = format!("m{}", i);
+            let value = format!("t{}", i);
+            assert_eq!(lookup(&catalog, key.as_bytes()), Some(value.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn entries_are_filtered_like_msgfmt_does() {
+        let source = r#"#, fuzzy
+msgid ""
+msgstr "Language: fr\n"
+
+msgid "translated"
+msgstr "traduit"
+
+#, fuzzy
+msgid "fuzzy"
+msgstr "flou"
+
+msgid "untranslated"
+msgstr ""
+
+msgid "one"
+msgid_plural "many"
+msgstr[0] "un"
+msgstr[1] ""
+
+msgid "no forms"
+msgid_plural "no forms either"
+msgstr[0] ""
+msgstr[1] "aucune"
+
+#~ msgid "obsolete"
+#~ msgstr "obsolète"
+"#;
+        let entries = po::parse(source).unwrap();
+        let included = |use_fuzzy| {
+            entries
+                .iter()
+                .filter(|entry| should_include(entry, use_fuzzy))
+                .map(|entry| entry.msgid.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(included(false), ["", "translated", "one"]);
+        assert_eq!(included(true), ["", "translated", "fuzzy", "one"]);
+    }
+
+    #[test]
+    fn hash_matches_gnu_gettext() {
+        assert_eq!(hash(b""), 0);
+        assert_eq!(hash(b"a"), 0x61);
+        assert_eq!(hash(b"ab"), 0x672);
+        assert_eq!(hash(b"ab\0cd"), 0x672);
+        assert_eq!(hash(b"Hello, world!"), hash_reference(b"Hello, world!"));
+    }
+
+    /// Straightforward port of `hash_string` from GNU gettext, with 64-bit `unsigned long`.
+    fn hash_reference(key: &[u8]) -> u32 {
+        let mut hval: u64 = 0;
+        for &byte in key {
+            hval <<= 4;
+            hval += u64::from(byte);
+            let g = hval & (!0u64 << 28);
+            if g != 0 {
+                hval ^= g >> 24;
+                hval ^= g;
+            }
+        }
+        hval as u32
+    }
+
+    #[test]
+    fn hash_table_sizes_are_prime() {
+        assert_eq!(hash_table_size(0), 3);
+        assert_eq!(hash_table_size(1), 3);
+        assert_eq!(hash_table_size(3), 5);
+        assert_eq!(hash_table_size(103), 137);
+    }
+}
@@ -0,0 +1,495 @@
+//! Parsing of PO files, which contain translations of the messages in a POT file.
+//!
+//! ```rust
+//! use gettext_tools::po;
+//!
+//! let entries = po::parse(
+//!     r#"
+//! msgid ""
+//! msgstr "Content-Type: text/plain; charset=UTF-8\n"
+//!
+//! #, fuzzy
+//! msgid "Hello, world!"
+//! msgstr "Bonjour le monde !"
+//!
+//! msgctxt "mail"
+//! msgid "One message"
+//! msgid_plural "Many messages"
+//! msgstr[0] "Un message"
+//! msgstr[1] "Plusieurs messages"
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! assert!(entries[0].is_header());
+//! assert!(entries[1].is_fuzzy());
+//! assert_eq!(entries[2].msgctxt.as_deref(), Some("mail"));
+//! assert_eq!(entries[2].msgstr, ["Un message", "Plusieurs messages"]);
+//! ```
+//!
+//! The files are expected to be in UTF-8. Comments other than flags (`#,`) are skipped, as are
+//! previous strings (`#|`). Obsolete entries (`#~`) are kept, but marked as such.
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+
+/// Errors that might come up while parsing a PO file. Each variant contains the 1-based number of
+/// the offending line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line is neither a comment, nor a keyword, nor a string.
+    InvalidLine(usize),
+    /// The string isn't terminated, contains an invalid escape, or is followed by something
+    /// other than whitespace.
+    InvalidString(usize),
+    /// The keyword is out of place, e.g. `msgstr` before `msgid`, or `msgstr[1]` before
+    /// `msgstr[0]`.
+    UnexpectedKeyword(usize),
+    /// The entry starting on this line lacks `msgstr`.
+    MissingMsgstr(usize),
+    /// The entry starting on this line has the same `msgctxt` and `msgid` as an earlier one.
+    DuplicateEntry(usize),
+}
+
+impl ParseError {
+    /// Returns the 1-based number of the line where the error was found.
+    pub fn line(&self) -> usize {
+        use self::ParseError::*;
+
+        match *self {
+            InvalidLine(line)
+            | InvalidString(line)
+            | UnexpectedKeyword(line)
+            | MissingMsgstr(line)
+            | DuplicateEntry(line) => line,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::ParseError::*;
+
+        match self {
+            InvalidLine(line) => write!(f, "Line {} is invalid.", line),
+            InvalidString(line) => write!(f, "The string on line {} is invalid.", line),
+            UnexpectedKeyword(line) => write!(f, "Unexpected keyword on line {}.", line),
+            MissingMsgstr(line) => write!(f, "The entry on line {} lacks msgstr.", line),
+            DuplicateEntry(line) => write!(f, "The entry on line {} is a duplicate.", line),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// An entry of a PO file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub msgctxt: Option<String>,
+    pub msgid: String,
+    pub msgid_plural: Option<String>,
+    /// The translation, or one translation per plural form if `msgid_plural` is present.
+    pub msgstr: Vec<String>,
+    /// Flags from `#,` comments, like `fuzzy` or `c-format`.
+    pub flags: Vec<String>,
+    /// Whether the entry is commented out with `#~`.
+    pub obsolete: bool,
+    /// 1-based number of the line where `msgctxt` or `msgid` of the entry is.
+    pub line: usize,
+}
+
+impl Entry {
+    /// Returns `true` if this is the header entry, i.e. its `msgid` is empty and it has no
+    /// context.
+    pub fn is_header(&self) -> bool {
+        self.msgctxt.is_none() && self.msgid.is_empty()
+    }
+
+    /// Returns `true` if the entry has the `fuzzy` flag, i.e. its translation needs to be
+    /// reviewed.
+    pub fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "fuzzy")
+    }
+
+    /// Returns `true` if the translation is non-empty. For plural messages, only the first form is
+    /// checked, like `msgfmt` does.
+    pub fn is_translated(&self) -> bool {
+        self.msgstr.first().is_some_and(|msgstr| !msgstr.is_empty())
+    }
+}
+
+/// Parses the contents of a PO file. Entries are returned in the order in which they appear in the
+/// file.
+pub fn parse(source: &str) -> Result<Vec<Entry>, ParseError> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+
+    let mut parser = Parser::default();
+    for (index, line) in source.lines().enumerate() {
+        parser.line(index + 1, line)?;
+    }
+    parser.finish_entry()?;
+
+    let mut seen = HashSet::new();
+    for entry in parser.entries.iter().filter(|entry| !entry.obsolete) {
+        if !seen.insert((&entry.msgctxt, &entry.msgid)) {
+            return Err(ParseError::DuplicateEntry(entry.line));
+        }
+    }
+
+    Ok(parser.entries)
+}
+
+/// The string that continuation lines are appended to.
+#[derive(Clone, Copy)]
+enum Field {
+    Msgctxt,
+    Msgid,
+    MsgidPlural,
+    Msgstr(usize),
+}
+
+#[derive(Default)]
+struct Parser {
+    entries: Vec<Entry>,
+    entry: Entry,
+    /// Whether `msgctxt` or `msgid` of the current entry was seen.
+    started: bool,
+    field: Option<Field>,
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, line: &str) -> Result<(), ParseError> {
+        let mut line = line.trim();
+        if line.is_empty() {
+            return self.finish_entry();
+        }
+
+        let obsolete = line.starts_with("#~");
+        if let Some(rest) = line.strip_prefix("#~") {
+            if rest.starts_with('|') {
+                return Ok(());
+            }
+            line = rest.trim();
+            if line.is_empty() {
+                return Ok(());
+            }
+        } else if line.starts_with('#') {
+            if !self.entry.msgstr.is_empty() {
+                self.finish_entry()?;
+            }
+            if let Some(flags) = line.strip_prefix("#,") {
+                self.entry.flags.extend(
+                    flags
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|flag| !flag.is_empty())
+                        .map(str::to_owned),
+                );
+            }
+            return Ok(());
+        }
+
+        if line.starts_with('"') {
+            let string = parse_string(line).ok_or(ParseError::InvalidString(number))?;
+            return match self.field {
+                Some(field) => {
+                    self.string_mut(field).push_str(&string);
+                    self.entry.obsolete |= obsolete;
+                    Ok(())
+                }
+                None => Err(ParseError::InvalidLine(number)),
+            };
+        }
+
+        let split = line
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .unwrap_or(line.len());
+        let (keyword, rest) = line.split_at(split);
+        if !keyword.starts_with("msg") {
+            return Err(ParseError::InvalidLine(number));
+        }
+        let string = parse_string(rest.trim_start()).ok_or(ParseError::InvalidString(number))?;
+        let unexpected = Err(ParseError::UnexpectedKeyword(number));
+
+        let field = match keyword {
+            "msgctxt" | "msgid" => {
+                if !self.entry.msgstr.is_empty() {
+                    self.finish_entry()?;
+                }
+                if keyword == "msgctxt" {
+                    if self.started {
+                        return unexpected;
+                    }
+                    self.entry.msgctxt = Some(String::new());
+                    Field::Msgctxt
+                } else {
+                    if self.has_msgid() {
+                        return unexpected;
+                    }
+                    Field::Msgid
+                }
+            }
+            "msgid_plural" => {
+                if !self.has_msgid() || self.entry.msgid_plural.is_some() {
+                    return unexpected;
+                }
+                self.entry.msgid_plural = Some(String::new());
+                Field::MsgidPlural
+            }
+            "msgstr" => {
+                if !self.has_msgid()
+                    || self.entry.msgid_plural.is_some()
+                    || !self.entry.msgstr.is_empty()
+                {
+                    return unexpected;
+                }
+                self.entry.msgstr.push(String::new());
+                Field::Msgstr(0)
+            }
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|index| index.trim().parse::<usize>().ok());
+                match index {
+                    Some(index) => {
+                        if self.entry.msgid_plural.is_none() || index != self.entry.msgstr.len() {
+                            return unexpected;
+                        }
+                        self.entry.msgstr.push(String::new());
+                        Field::Msgstr(index)
+                    }
+                    None => return Err(ParseError::InvalidLine(number)),
+                }
+            }
+        };
+
+        if !self.started {
+            self.started = true;
+            self.entry.line = number;
+        }
+        self.string_mut(field).push_str(&string);
+        self.field = Some(field);
+        self.entry.obsolete |= obsolete;
+
+        Ok(())
+    }
+
+    /// Returns `true` if `msgid` of the current entry was seen.
+    fn has_msgid(&self) -> bool {
+        match self.field {
+            Some(Field::Msgctxt) | None => false,
+            Some(_) => true,
+        }
+    }
+
+    fn string_mut(&mut self, field: Field) -> &mut String {
+        match field {
+            Field::Msgctxt => self.entry.msgctxt.get_or_insert_with(String::new),
+            Field::Msgid => &mut self.entry.msgid,
+            Field::MsgidPlural => self.entry.msgid_plural.get_or_insert_with(String::new),
+            Field::Msgstr(index) => &mut self.entry.msgstr[index],
+        }
+    }
+
+    fn finish_entry(&mut self) -> Result<(), ParseError> {
+        if self.started {
+            if self.entry.msgstr.is_empty() {
+                return Err(ParseError::MissingMsgstr(self.entry.line));
+            }
+            self.entries.push(std::mem::take(&mut self.entry));
+        } else {
+            // Comments that aren't followed by an entry, e.g. at the end of the file.
+            self.entry = Entry::default();
+        }
+        self.started = false;
+        self.field = None;
+
+        Ok(())
+    }
+}
+
+/// Parses a quoted string with C escapes, which must be the only thing on the line.
+fn parse_string(line: &str) -> Option<String> {
+    let mut chars = line.strip_prefix('"')?.chars();
+    let mut string = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => {
+                let c = match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'a' => '\x07',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'v' => '\x0b',
+                    c @ ('\\' | '"' | '\'' | '?') => c,
+                    c @ '0'..='7' => {
+                        let mut value = c.to_digit(8)?;
+                        for _ in 0..2 {
+                            match chars.clone().next().and_then(|c| c.to_digit(8)) {
+                                Some(digit) => {
+                                    value = value * 8 + digit;
+                                    chars.next();
+                                }
+                                None => break,
+                            }
+                        }
+                        ascii(value)?
+                    }
+                    'x' => {
+                        let mut value = 0;
+                        let mut digits = 0;
+                        while let Some(digit) = chars.clone().next().and_then(|c| c.to_digit(16)) {
+                            value = value * 16 + digit;
+                            digits += 1;
+                            chars.next();
+                            if digits == 2 {
+                                break;
+                            }
+                        }
+                        if digits == 0 {
+                            return None;
+                        }
+                        ascii(value)?
+                    }
+                    _ => return None,
+                };
+                string.push(c);
+            }
+            c => string.push(c),
+        }
+    }
+
+    if chars.as_str().trim().is_empty() {
+        Some(string)
+    } else {
+        None
+    }
+}
+
+/// Escapes can only produce ASCII characters, as other bytes would make the string invalid UTF-8.
+fn ascii(value: u32) -> Option<char> {
+    if value < 0x80 {
+        char::from_u32(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(msgctxt: Option<&str>, msgid: &str, msgstr: &[&str], line: usize) -> Entry {
+        Entry {
+            msgctxt: msgctxt.map(str::to_owned),
+            msgid: msgid.to_owned(),
+            msgstr: msgstr.iter().map(|msgstr| msgstr.to_string()).collect(),
+            line,
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn entries_are_parsed() {
+        let source = r#"# Translator comment
+#. Extracted comment
+#: src/main.rs:1
+#, fuzzy, c-format
+#| msgid "Hello"
+msgid "Hello, "
+"world!\n"
+msgstr ""
+"Bonjour le monde !\n"
+
+msgctxt "mail"
+msgid "message"
+msgstr "courriel"
+
+msgid "apple"
+msgid_plural "apples"
+msgstr[0] "pomme"
+msgstr[1] "pommes"
+msgid "right after"
+msgstr "juste après"
+
+#~ msgid "old"
+#~ msgstr ""
+#~ "vieux"
+#~ msgid "older"
+#~ msgstr "plus vieux"
+"#;
+        let entries = parse(source).unwrap();
+
+        let mut hello = entry(None, "Hello, world!\n", &["Bonjour le monde !\n"], 6);
+        hello.flags = vec!["fuzzy".to_owned(), "c-format".to_owned()];
+        let mut apple = entry(None, "apple", &["pomme", "pommes"], 15);
+        apple.msgid_plural = Some("apples".to_owned());
+        let mut old = entry(None, "old", &["vieux"], 22);
+        old.obsolete = true;
+        let mut older = entry(None, "older", &["plus vieux"], 25);
+        older.obsolete = true;
+        assert_eq!(
+            entries,
+            [
+                hello,
+                entry(Some("mail"), "message", &["courriel"], 11),
+                apple,
+                entry(None, "right after", &["juste après"], 19),
+                old,
+                older,
+            ]
+        );
+        assert!(entries[0].is_fuzzy());
+        assert!(!entries[1].is_fuzzy());
+    }
+
+    #[test]
+    fn strings_are_unescaped() {
+        assert_eq!(
+            parse_string(r#""a\tb\\c\"d\101\x42\n" "#).as_deref(),
+            Some("a\tb\\c\"dAB\n")
+        );
+        assert_eq!(parse_string(r#""unterminated"#), None);
+        assert_eq!(parse_string(r#""trailing" garbage"#), None);
+        assert_eq!(parse_string(r#""\q""#), None);
+        assert_eq!(parse_string(r#""\377""#), None);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        assert_eq!(
+            parse("msgid \"a\"\nmsgstr \"b\"\ngarbage\n"),
+            Err(ParseError::InvalidLine(3))
+        );
+        assert_eq!(parse("\"orphan\"\n"), Err(ParseError::InvalidLine(1)));
+        assert_eq!(
+            parse("msgstr \"b\"\n"),
+            Err(ParseError::UnexpectedKeyword(1))
+        );
+        assert_eq!(
+            parse("msgid \"a\"\nmsgid_plural \"as\"\nmsgstr[1] \"b\"\n"),
+            Err(ParseError::UnexpectedKeyword(3))
+        );
+        assert_eq!(
+            parse("msgid \"a\"\nmsgstr[0] \"b\"\n"),
+            Err(ParseError::UnexpectedKeyword(2))
+        );
+        assert_eq!(
+            parse("\n\nmsgid \"a\"\n\nmsgid \"b\"\nmsgstr \"c\"\n"),
+            Err(ParseError::MissingMsgstr(3))
+        );
+        assert_eq!(
+            parse("msgid \"a\"\nmsgstr \"b\"\n\nmsgid \"a\"\nmsgstr \"c\"\n"),
+            Err(ParseError::DuplicateEntry(4))
+        );
+        assert_eq!(
+            parse("msgid \"a\nmsgstr \"b\"\n"),
+            Err(ParseError::InvalidString(1))
+        );
+    }
+}
@@ -16,6 +16,11 @@ fn main() {
         cfg.include(&out);
     }
 
+    // The relocation API is only declared for the bundled libintl.
+    if env::var_os("DEP_GETTEXT_RELOCATABLE").is_some() {
+        cfg.cfg("gettext_relocatable", None);
+    }
+
     cfg.header("locale.h");
     cfg.header("libintl.h");
 